@@ -0,0 +1,177 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{prefixed, prefixed_read, typed, typed_read};
+
+use crate::conf::MAX_BETS_PER_GAME;
+use crate::msg::BetSide;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bet {
+    pub bettor: HumanAddr,
+    pub side: BetSide,
+    pub amount: Uint128,
+}
+
+/// Spectator bets on a game, paid out parimutuel-style when the game ends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BetPool {
+    #[serde(skip)]
+    game: [u8; 32],
+    pub bets: Vec<Bet>,
+    pub settled: bool,
+}
+
+impl BetPool {
+    pub fn new(game: [u8; 32]) -> BetPool {
+        BetPool {
+            game: game,
+            bets: vec![],
+            settled: false,
+        }
+    }
+
+    pub fn place(&mut self, bettor: HumanAddr, side: BetSide, amount: Uint128) -> StdResult<()> {
+        if self.settled {
+            return Err(StdError::generic_err("forbidden bets settled"));
+        }
+        match self
+            .bets
+            .iter_mut()
+            .find(|b| b.bettor == bettor && b.side == side)
+        {
+            Some(bet) => bet.amount = Uint128(bet.amount.u128() + amount.u128()),
+            None => {
+                if self.bets.len() >= MAX_BETS_PER_GAME {
+                    return Err(StdError::generic_err("forbidden too many bets"));
+                }
+                self.bets.push(Bet {
+                    bettor,
+                    side,
+                    amount,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn total(&self, side: BetSide) -> Uint128 {
+        Uint128(
+            self.bets
+                .iter()
+                .filter(|b| b.side == side)
+                .map(|b| b.amount.u128())
+                .sum(),
+        )
+    }
+
+    /**
+     * Splits the whole pool between backers of the winner in proportion to their bets.
+     * Everyone is refunded on a draw or when nobody backed the winner.
+     * Rounding remainder goes to the first winning backer so that the pool is always emptied.
+     */
+    pub fn settle(&mut self, winner: Option<BetSide>) -> Vec<(HumanAddr, Uint128)> {
+        if self.settled {
+            return vec![];
+        }
+        self.settled = true;
+        let pool: u128 = self.bets.iter().map(|b| b.amount.u128()).sum();
+        let winner = match winner {
+            Some(side) if !self.total(side).is_zero() => side,
+            _ => {
+                return self
+                    .bets
+                    .iter()
+                    .map(|b| (b.bettor.clone(), b.amount))
+                    .collect()
+            }
+        };
+        let winning_total = self.total(winner).u128();
+        let mut shares: Vec<(HumanAddr, Uint128)> = self
+            .bets
+            .iter()
+            .filter(|b| b.side == winner)
+            .map(|b| {
+                (
+                    b.bettor.clone(),
+                    Uint128(b.amount.u128() * pool / winning_total),
+                )
+            })
+            .collect();
+        let paid: u128 = shares.iter().map(|(_, a)| a.u128()).sum();
+        shares[0].1 = Uint128(shares[0].1.u128() + pool - paid);
+        shares
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) {
+        let mut space = prefixed(b"bets", storage);
+        let mut bucket = typed::<_, BetPool>(&mut space);
+        bucket.save(&self.game, &self).unwrap();
+    }
+
+    pub fn load<S: Storage>(storage: &S, game: [u8; 32]) -> StdResult<Self> {
+        Ok(Self::may_load(storage, game)?.unwrap_or_else(|| BetPool::new(game)))
+    }
+
+    pub fn may_load<S: Storage>(storage: &S, game: [u8; 32]) -> StdResult<Option<Self>> {
+        let mut space = prefixed_read(b"bets", storage);
+        let bucket = typed_read::<_, BetPool>(&mut space);
+        bucket
+            .may_load(&game)
+            .map(|maybe| maybe.map(|data| Self { game, ..data }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_total(payouts: &[(HumanAddr, Uint128)]) -> u128 {
+        payouts.iter().map(|(_, a)| a.u128()).sum()
+    }
+
+    #[test]
+    fn parimutuel_split() {
+        let mut pool = BetPool::new([0u8; 32]);
+        pool.place(HumanAddr::from("a"), BetSide::Player1, Uint128(100))
+            .unwrap();
+        pool.place(HumanAddr::from("b"), BetSide::Player1, Uint128(200))
+            .unwrap();
+        pool.place(HumanAddr::from("c"), BetSide::Player2, Uint128(301))
+            .unwrap();
+
+        let payouts = pool.settle(Some(BetSide::Player1));
+        assert_eq!(payouts.len(), 2);
+        assert_eq!(payouts[0], (HumanAddr::from("a"), Uint128(201)));
+        assert_eq!(payouts[1], (HumanAddr::from("b"), Uint128(400)));
+        assert_eq!(pool_total(&payouts), 601);
+
+        // Can't settle twice
+        assert_eq!(pool.settle(Some(BetSide::Player1)).len(), 0);
+    }
+
+    #[test]
+    fn refund_without_winning_backers() {
+        let mut pool = BetPool::new([0u8; 32]);
+        pool.place(HumanAddr::from("a"), BetSide::Player1, Uint128(100))
+            .unwrap();
+        pool.place(HumanAddr::from("a"), BetSide::Player1, Uint128(50))
+            .unwrap();
+        pool.place(HumanAddr::from("b"), BetSide::Player1, Uint128(200))
+            .unwrap();
+
+        let payouts = pool.settle(Some(BetSide::Player2));
+        assert_eq!(payouts[0], (HumanAddr::from("a"), Uint128(150)));
+        assert_eq!(payouts[1], (HumanAddr::from("b"), Uint128(200)));
+
+        let mut pool = BetPool::new([0u8; 32]);
+        pool.place(HumanAddr::from("a"), BetSide::Player1, Uint128(100))
+            .unwrap();
+        pool.place(HumanAddr::from("b"), BetSide::Player2, Uint128(200))
+            .unwrap();
+        let payouts = pool.settle(None);
+        assert_eq!(payouts[0], (HumanAddr::from("a"), Uint128(100)));
+        assert_eq!(payouts[1], (HumanAddr::from("b"), Uint128(200)));
+    }
+}
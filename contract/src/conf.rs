@@ -2,8 +2,46 @@ pub const FUNDING_AMOUNT: u128 = 10_000_000;
 pub const FUNDING_DENOM: &str = "uscrt";
 pub const WINS_TO_FINISH: u8 = 3;
 
-/// Number of blocks from last activity after which player waiting for other player's move can claim victory
+/// Blocks each player has in their time bank at the start of the game.
+/// Player waiting for other player's move can claim victory once the other player's bank runs out.
 pub const PLAYER_DEADLINE_BLOCKS: u64 = 20;
 
+/// Blocks added to player's time bank after each move
+pub const PLAYER_INCREMENT_BLOCKS: u64 = 5;
+
+/// Bounds for deadlines chosen by private game creators
+pub const MIN_DEADLINE_BLOCKS: u64 = 5;
+pub const MAX_DEADLINE_BLOCKS: u64 = 14_400;
+pub const MIN_DEADLINE_SECONDS: u64 = 30;
+pub const MAX_DEADLINE_SECONDS: u64 = 86_400;
+
 /// Number of blocks from last activity after which any address can shutdown the game and earn a fee
 pub const GLOBAL_DEADLINE_BLOCKS: u64 = 300;
+
+/// Maximum number of games in a best-of series
+pub const MAX_SERIES_GAMES: u8 = 9;
+
+/// Page sizes for listing queries
+pub const DEFAULT_LIST_LIMIT: u32 = 10;
+pub const MAX_LIST_LIMIT: u32 = 30;
+/// Maximum number of index entries a listing query scans for filtered results
+pub const MAX_LIST_SCAN: u64 = 200;
+
+/// Most the house can put at stake across games started in one block
+pub const MAX_HOUSE_EXPOSURE_PER_BLOCK: u128 = 10 * FUNDING_AMOUNT;
+
+/// Maximum number of locators claimed in one ClaimMany, bounds gas
+pub const MAX_CLAIMS_PER_BATCH: usize = 20;
+
+/// Maximum number of distinct spectator bets on one game, bounds settlement gas
+pub const MAX_BETS_PER_GAME: usize = 50;
+
+/// Bounds for the number of players in a room
+pub const MIN_ROOM_PLAYERS: u8 = 3;
+pub const MAX_ROOM_PLAYERS: u8 = 8;
+
+/// Duels a team needs to win, ahead of the other team, to finish a team game
+pub const TEAM_WINS_TO_FINISH: u8 = 5;
+
+/// Bech32 prefix of the account addresses that sign query permits
+pub const ADDRESS_PREFIX: &str = "secret";
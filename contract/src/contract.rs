@@ -4,16 +4,43 @@ use cosmwasm_std::{
 };
 extern crate hex;
 
-use crate::conf::{FUNDING_AMOUNT, FUNDING_DENOM, PLAYER_DEADLINE_BLOCKS, WINS_TO_FINISH};
-use crate::msg::{GameLobbyResponse, GameStatusResponse, HandleMsg, Handsign, InitMsg, QueryMsg};
-use crate::state::{lobby_game, Game, Locator};
+use crate::bets::BetPool;
+use crate::conf::{
+    DEFAULT_LIST_LIMIT, FUNDING_AMOUNT, FUNDING_DENOM, MAX_CLAIMS_PER_BATCH,
+    MAX_HOUSE_EXPOSURE_PER_BLOCK, MAX_LIST_LIMIT, MAX_LIST_SCAN, MAX_ROOM_PLAYERS,
+    MAX_SERIES_GAMES, MIN_ROOM_PLAYERS, WINS_TO_FINISH,
+};
+use crate::entropy;
+use crate::msg::{
+    BetSide, BetsResponse, ClaimResult, ClaimabilityResponse, DeadlinePolicy, DeadlineUnit,
+    EndReason, GameFilter, GameLobbyResponse, GamePhase, GameStatusResponse, GameSummary,
+    HandleAnswer, HandleMsg, Handsign, HouseResponse, InitMsg, ListGamesResponse,
+    ListOpenPrivateGamesResponse, MyGameStatusResponse, MyGamesResponse, OpenPrivateGame,
+    Permission, Permit, PermitQuery, PlayerGame, PlayerStatus, QueryMsg, RematchStatus,
+    RoomPlayerStatus, RoomStatusResponse, SeriesStatusResponse, Team, TeamGameStatusResponse,
+    TeamPlayerStatus,
+};
+use crate::permit;
+use crate::room::{may_load_room_code, save_room_code, Room};
+use crate::state::{
+    admin, contract_address, index_get, index_len, load_admin, load_contract_address,
+    load_player_stats, load_stats, lobby_game, next_game_id, player_index, push_index,
+    update_player_stats, update_stats, Game, House, Invite, Locator, Rematch, Series, SessionKey,
+    Settlement, GAME_INDEX, OPEN_PRIVATE_INDEX,
+};
+use crate::team::TeamGame;
+use sha2::{Digest, Sha256};
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    _env: Env,
+    env: Env,
     _msg: InitMsg,
 ) -> StdResult<InitResponse> {
-    lobby_game(&mut deps.storage).save(&None)?;
+    lobby_game(&mut deps.storage, false).save(&None)?;
+    lobby_game(&mut deps.storage, true).save(&None)?;
+    admin(&mut deps.storage).save(&env.message.sender)?;
+    contract_address(&mut deps.storage).save(&env.contract.address)?;
+    entropy::contribute(&mut deps.storage, &env, None)?;
     Ok(InitResponse::default())
 }
 
@@ -22,11 +49,81 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
+    entropy::contribute(&mut deps.storage, &env, msg.entropy())?;
     match msg {
-        HandleMsg::JoinGame { locator } => join_game(deps, env, locator),
-        HandleMsg::PrivateGame { locator } => private_game(deps, env, locator),
-        HandleMsg::PlayHand { locator, handsign } => play_hand(deps, env, locator, handsign),
-        HandleMsg::ClaimInactivity { locator } => claim_inactivity(deps, env, locator),
+        HandleMsg::JoinGame {
+            locator, free_play, ..
+        } => join_game(deps, env, locator, free_play),
+        HandleMsg::PrivateGame {
+            locator,
+            invite,
+            stake,
+            deadline,
+            series,
+            discoverable,
+            ..
+        } => private_game(
+            deps,
+            env,
+            locator,
+            invite,
+            stake,
+            PrivateGameOptions {
+                deadline,
+                series,
+                discoverable,
+            },
+        ),
+        HandleMsg::PlayHand {
+            locator, handsign, ..
+        } => play_hand(deps, env, locator, handsign),
+        HandleMsg::ClaimInactivity { locator, .. } => claim_inactivity(deps, env, locator),
+        HandleMsg::ClaimMany { locators, .. } => claim_many(deps, env, locators),
+        HandleMsg::PlaceBet { locator, side, .. } => place_bet(deps, env, locator, side),
+        HandleMsg::OfferRematch {
+            locator,
+            new_locator,
+            ..
+        } => offer_rematch(deps, env, locator, new_locator),
+        HandleMsg::AcceptRematch {
+            locator,
+            new_locator,
+            ..
+        } => accept_rematch(deps, env, locator, new_locator),
+        HandleMsg::PlayHouse { locator, stake, .. } => play_house(deps, env, locator, stake),
+        HandleMsg::ResolveHouse { locator, .. } => resolve_house(deps, env, locator),
+        HandleMsg::FundHouse { .. } => fund_house(deps, env),
+        HandleMsg::WithdrawHouse { amount, .. } => withdraw_house(deps, env, amount),
+        HandleMsg::AuthorizeSessionKey {
+            locator,
+            session_addr,
+            expires_at,
+            ..
+        } => authorize_session_key(deps, env, locator, session_addr, expires_at),
+        HandleMsg::OpenRoom {
+            locator,
+            room,
+            size,
+            stake,
+            deadline,
+            ..
+        } => open_room(deps, env, locator, room, size, stake, deadline),
+        HandleMsg::JoinRoom { locator, room, .. } => join_room(deps, env, locator, room),
+        HandleMsg::OpenTeamGame {
+            locator,
+            room,
+            team,
+            stake,
+            deadline,
+            ..
+        } => open_team_game(deps, env, locator, room, team, stake, deadline),
+        HandleMsg::JoinTeamGame {
+            locator,
+            room,
+            team,
+            ..
+        } => join_team_game(deps, env, locator, room, team),
+        HandleMsg::RevokePermit { permit_name, .. } => revoke_permit(deps, env, permit_name),
     }
 }
 
@@ -36,25 +133,35 @@ pub fn play_hand<S: Storage, A: Api, Q: Querier>(
     locator: String,
     handsign: Handsign,
 ) -> StdResult<HandleResponse> {
-    let mut bytes = [0u8; 32];
-    match hex::decode_to_slice(locator, &mut bytes as &mut [u8]) {
-        Err(_) => return Err(StdError::generic_err("bad_request invalid_locator")),
-        Ok(_) => (),
-    }
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
     let locator = Locator::load(&deps.storage, bytes)?;
+    if let Some(room) = Room::may_load(&deps.storage, locator.game)? {
+        return play_room(deps, env, locator.game, room, handsign);
+    }
+    if let Some(team_game) = TeamGame::may_load(&deps.storage, locator.game)? {
+        return play_team_game(deps, env, locator.game, team_game, handsign);
+    }
     let mut game = Game::load(&deps.storage, locator.game)?;
 
     let mut pay_address = None;
     if game.game_over {
         return Err(StdError::generic_err("game_over"));
     }
-    if env.message.sender == game.player1 && !game.player1_handsign.is_none()
-        || env.message.sender == game.player2 && !game.player2_handsign.is_none()
+    let player = acting_player(&deps.storage, &env, locator.game, &game)?;
+    let round = game.round;
+    if player == game.player1 && !game.player1_handsign.is_none()
+        || player == game.player2 && game.player2_played()
     {
         return Err(StdError::generic_err("already_played"));
     }
-
-    if env.message.sender == game.player1 {
+    if game.house {
+        // house draws its move now but the round only resolves in a later block, so a contract
+        // playing the house can't see the outcome in a call it could still abort
+        let input = [&locator.game[..], &[game.round, handsign as u8]].concat();
+        game.player1_handsign = Some(handsign);
+        game.player2_handsign = Some(house_handsign(&mut deps.storage, &input)?);
+        game.house_move_height = Some(env.block.height);
+    } else if player == game.player1 {
         match game.player2_handsign {
             None => {
                 game.player1_handsign = Some(handsign);
@@ -69,7 +176,7 @@ pub fn play_hand<S: Storage, A: Api, Q: Querier>(
                 }
             }
         }
-    } else if env.message.sender == game.player2 {
+    } else {
         match game.player1_handsign {
             None => {
                 game.player2_handsign = Some(handsign);
@@ -84,103 +191,545 @@ pub fn play_hand<S: Storage, A: Api, Q: Querier>(
                 }
             }
         }
-    } else {
-        return Err(StdError::generic_err("You are not a player"));
     }
-    game.last_play_height = env.block.height;
+    let now = game.deadline.now(&env);
+    game.charge_clock(&player, now);
+    let round_resolved = game.player1_handsign.is_none() && game.player2_handsign.is_none();
+    if round_resolved {
+        // round resolved, both clocks start running
+        game.round_start = now;
+    }
     if game.player1_wins == WINS_TO_FINISH {
         pay_address = Some(game.player1.clone());
-        game.game_over = true;
     } else if game.player2_wins == WINS_TO_FINISH {
         pay_address = Some(game.player2.clone());
-        game.game_over = true;
     }
-    game.save(&mut deps.storage);
+    let (wins, opponent_wins) = match player == game.player1 {
+        true => (game.player1_wins, game.player2_wins),
+        false => (game.player2_wins, game.player1_wins),
+    };
+    let mut res = HandleResponse::default();
     match pay_address {
-        None => {}
+        None => {
+            game.save(&mut deps.storage);
+        }
         Some(address) => {
-            return Ok(payout(
-                env.contract.address,
+            res = end_game(
+                &mut deps.storage,
+                &env,
+                locator.game,
+                game,
                 address,
-                Uint128(FUNDING_AMOUNT * 2),
+                EndReason::WinsReached,
+            )?;
+        }
+    };
+    // a series starts its next game in place of the finished one
+    let game_over = Game::load(&deps.storage, locator.game)?.game_over;
+    answer(
+        res,
+        HandleAnswer::PlayHand {
+            round_resolved,
+            round,
+            wins,
+            opponent_wins,
+            game_over,
+        },
+    )
+}
+
+pub fn resolve_house<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+) -> StdResult<HandleResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let mut game = Game::load(&deps.storage, locator.game)?;
+    if game.game_over {
+        return Err(StdError::generic_err("game_over"));
+    }
+    acting_player(&deps.storage, &env, locator.game, &game)?;
+    let (handsign, house_handsign) = match (
+        game.house_move_height,
+        game.player1_handsign,
+        game.player2_handsign,
+    ) {
+        (Some(height), _, _) if env.block.height <= height => {
+            return Err(StdError::generic_err(
+                "forbidden house move resolves in a later block",
+            ))
+        }
+        (Some(_), Some(handsign), Some(house_handsign)) => (handsign, house_handsign),
+        _ => {
+            return Err(StdError::generic_err(
+                "bad_request no house move to resolve",
             ))
         }
     };
-    Ok(HandleResponse::default())
+    let round = game.round;
+    game.round += 1;
+    game.player1_handsign = None;
+    game.player2_handsign = None;
+    game.house_move_height = None;
+    if handsign.beats(house_handsign) {
+        game.player1_wins += 1;
+    } else if handsign != house_handsign {
+        game.player2_wins += 1;
+    }
+    game.round_start = game.deadline.now(&env);
+    let (wins, house_wins) = (game.player1_wins, game.player2_wins);
+    let winner = if wins == WINS_TO_FINISH {
+        Some(game.player1.clone())
+    } else if house_wins == WINS_TO_FINISH {
+        Some(game.player2.clone())
+    } else {
+        None
+    };
+    let game_over = winner.is_some();
+    let res = match winner {
+        None => {
+            game.save(&mut deps.storage);
+            HandleResponse::default()
+        }
+        Some(winner) => end_game(
+            &mut deps.storage,
+            &env,
+            locator.game,
+            game,
+            winner,
+            EndReason::WinsReached,
+        )?,
+    };
+    answer(
+        res,
+        HandleAnswer::ResolveHouse {
+            round,
+            house_handsign,
+            wins,
+            house_wins,
+            game_over,
+        },
+    )
 }
 
-pub fn join_game<S: Storage, A: Api, Q: Querier>(
+pub fn play_house<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     locator: String,
+    stake: Option<Uint128>,
 ) -> StdResult<HandleResponse> {
-    let funds = &env.message.sent_funds[0];
-    if funds.denom != FUNDING_DENOM || funds.amount < Uint128(FUNDING_AMOUNT) {
-        return Err(StdError::generic_err(
-            "insufficient_funds 100000 uscrt required",
-        ));
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
+    }
+    let stake = stake.unwrap_or(Uint128(FUNDING_AMOUNT));
+    check_funds(&env, stake)?;
+    let mut house = House::load(&deps.storage)?;
+    house.reserve(stake, env.block.height)?;
+    house.save(&mut deps.storage)?;
+
+    let game_id = next_game_id(&mut deps.storage)?;
+    let deadline = DeadlinePolicy::default();
+    let mut locator = Locator::new(loc_b, game_id, env.message.sender.clone(), stake, deadline);
+    locator.settlement = Settlement::Matched;
+    locator.create(&mut deps.storage)?;
+    let mut game = Game::new(
+        game_id,
+        env.message.sender.clone(),
+        env.contract.address.clone(),
+        stake,
+        deadline,
+        &env,
+        vec![loc_b],
+    );
+    game.house = true;
+    game.save(&mut deps.storage);
+    push_index(&mut deps.storage, GAME_INDEX, game_id)?;
+    update_stats(&mut deps.storage, |s| {
+        s.stake(stake);
+        s.stake(stake);
+        s.games_created += 1;
+        s.house_games += 1;
+    })?;
+    answer(HandleResponse::default(), HandleAnswer::PlayHouse { stake })
+}
+
+pub fn fund_house<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    if env.message.sender != load_admin(&deps.storage)? {
+        return Err(StdError::generic_err("forbidden admin only"));
+    }
+    let amount = match env.message.sent_funds.first() {
+        Some(funds) if funds.denom == FUNDING_DENOM && !funds.amount.is_zero() => funds.amount,
+        _ => {
+            return Err(StdError::generic_err(format!(
+                "insufficient_funds {} required",
+                FUNDING_DENOM
+            )))
+        }
+    };
+    let mut house = House::load(&deps.storage)?;
+    house.bankroll += amount;
+    house.save(&mut deps.storage)?;
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::FundHouse {
+            bankroll: house.bankroll,
+        },
+    )
+}
+
+pub fn withdraw_house<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    if env.message.sender != load_admin(&deps.storage)? {
+        return Err(StdError::generic_err("forbidden admin only"));
+    }
+    let mut house = House::load(&deps.storage)?;
+    if amount.u128() > house.bankroll.u128() {
+        return Err(StdError::generic_err("forbidden house bankroll too low"));
     }
-    let mut loc_b = [0u8; 32];
-    match hex::decode_to_slice(locator, &mut loc_b as &mut [u8]) {
-        Err(_) => return Err(StdError::generic_err("bad_request invalid_locator")),
-        Ok(_) => (),
+    house.bankroll = Uint128(house.bankroll.u128() - amount.u128());
+    house.save(&mut deps.storage)?;
+    answer(
+        payout(env.contract.address, env.message.sender, amount),
+        HandleAnswer::WithdrawHouse {
+            bankroll: house.bankroll,
+        },
+    )
+}
+
+pub fn join_game<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    free_play: bool,
+) -> StdResult<HandleResponse> {
+    let stake = match free_play {
+        false => Uint128(FUNDING_AMOUNT),
+        true => Uint128(0),
+    };
+    check_funds(&env, stake)?;
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
     }
 
-    match lobby_game(&mut deps.storage).load()? {
+    let game_started = match lobby_game(&mut deps.storage, free_play).load()? {
         None => {
             // player1 goes to lobby to wait for player2
-            Locator::new(loc_b, loc_b, env.message.sender).save(&mut deps.storage);
-            lobby_game(&mut deps.storage).save(&Some(loc_b))?;
+            let deadline = DeadlinePolicy::default();
+            let game_id = next_game_id(&mut deps.storage)?;
+            Locator::new(loc_b, game_id, env.message.sender, stake, deadline)
+                .create(&mut deps.storage)?;
+            update_stats(&mut deps.storage, |s| s.stake(stake))?;
+            lobby_game(&mut deps.storage, free_play).save(&Some(loc_b))?;
+            false
         }
         Some(s) => {
             // player2 joins player1 and lobby becomes empty
-            let p1_locator = Locator::load(&mut deps.storage, s)?;
-            if p1_locator.canceled {
+            let mut p1_locator = Locator::load(&mut deps.storage, s)?;
+            if p1_locator.settlement != Settlement::Open {
                 return Err(StdError::generic_err("forbidden game canceled"));
             }
+            p1_locator.settlement = Settlement::Matched;
+            p1_locator.save(&mut deps.storage);
             let game_id = p1_locator.game;
-            let p2_locator = Locator::new(loc_b, game_id, env.message.sender);
-            p2_locator.save(&mut deps.storage);
-            let game = Game::new(game_id, p1_locator.player, p2_locator.player);
+            let mut p2_locator = Locator::new(
+                loc_b,
+                game_id,
+                env.message.sender.clone(),
+                stake,
+                p1_locator.deadline,
+            );
+            p2_locator.settlement = Settlement::Matched;
+            p2_locator.create(&mut deps.storage)?;
+            let game = Game::new(
+                game_id,
+                p1_locator.player,
+                p2_locator.player,
+                stake,
+                p1_locator.deadline,
+                &env,
+                vec![s, loc_b],
+            );
             game.save(&mut deps.storage);
-            lobby_game(&mut deps.storage).save(&None)?;
+            push_index(&mut deps.storage, GAME_INDEX, game_id)?;
+            update_stats(&mut deps.storage, |s| {
+                s.stake(stake);
+                s.games_created += 1;
+                s.public_games += 1;
+            })?;
+            lobby_game(&mut deps.storage, free_play).save(&None)?;
+            true
         }
     };
 
-    Ok(HandleResponse::default())
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::JoinGame {
+            game_started,
+            player1: !game_started,
+        },
+    )
+}
+
+/// Options of a private game that only its creator sets, the player who joins takes them as they are
+pub struct PrivateGameOptions {
+    pub deadline: Option<DeadlinePolicy>,
+    pub series: Option<u8>,
+    pub discoverable: bool,
 }
 
 pub fn private_game<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     locator: String,
+    invite: String,
+    stake: Option<Uint128>,
+    options: PrivateGameOptions,
 ) -> StdResult<HandleResponse> {
-    let funds = &env.message.sent_funds[0];
-    if funds.denom != FUNDING_DENOM || funds.amount < Uint128(FUNDING_AMOUNT) {
+    let PrivateGameOptions {
+        deadline,
+        series,
+        discoverable,
+    } = options;
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let invite_b = decode_hex32(&invite, "bad_request invalid_invite")?;
+    if invite_b == loc_b {
         return Err(StdError::generic_err(
-            "insufficient_funds 100000 uscrt required",
+            "bad_request invite must differ from locator",
         ));
     }
-    let mut loc_b = [0u8; 32];
-    match hex::decode_to_slice(locator, &mut loc_b as &mut [u8]) {
-        Err(_) => return Err(StdError::generic_err("bad_request invalid_locator")),
-        Ok(_) => (),
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
     }
-    match Locator::may_load(&deps.storage, loc_b)? {
+    let game_started = match Invite::may_load(&deps.storage, invite_b)? {
         None => {
-            // player1 waits for player2
-            Locator::new(loc_b, loc_b, env.message.sender).save(&mut deps.storage);
+            // player1 sets the stake and waits for player2
+            let stake = stake.unwrap_or(Uint128(FUNDING_AMOUNT));
+            let deadline = deadline.unwrap_or_default();
+            deadline.validate()?;
+            if let Some(games) = series {
+                if games % 2 == 0 || !(3..=MAX_SERIES_GAMES).contains(&games) {
+                    return Err(StdError::generic_err(format!(
+                        "bad_request series must be an odd number of games from 3 to {}",
+                        MAX_SERIES_GAMES
+                    )));
+                }
+            }
+            check_funds(&env, stake)?;
+            let game_id = next_game_id(&mut deps.storage)?;
+            let mut locator = Locator::new(loc_b, game_id, env.message.sender, stake, deadline);
+            locator.series = series;
+            locator.create(&mut deps.storage)?;
+            Invite::new(invite_b, loc_b).save(&mut deps.storage);
+            update_stats(&mut deps.storage, |s| s.stake(stake))?;
+            if discoverable {
+                push_index(&mut deps.storage, OPEN_PRIVATE_INDEX, invite_b)?;
+            }
+            false
         }
-        Some(l) => {
-            // player2 joins player1
-            if l.canceled {
+        Some(mut invite) => {
+            // player2 matches player1's stake and takes the seat with their own locator
+            if invite.used {
+                return Err(StdError::generic_err("forbidden invite used"));
+            }
+            let mut l = Locator::load(&deps.storage, invite.locator)?;
+            if l.settlement == Settlement::Refunded {
                 return Err(StdError::generic_err("forbidden game canceled"));
             }
-            let game = Game::new(l.game, l.player, env.message.sender);
+            if l.settlement != Settlement::Open {
+                return Err(StdError::generic_err("forbidden game already started"));
+            }
+            if l.player == env.message.sender {
+                return Err(StdError::generic_err("forbidden can't join own game"));
+            }
+            if stake.map_or(false, |s| s != l.stake) {
+                return Err(StdError::generic_err(format!(
+                    "bad_request stake is {} {}",
+                    l.stake, FUNDING_DENOM
+                )));
+            }
+            check_funds(&env, l.stake)?;
+            let mut game = Game::new(
+                l.game,
+                l.player.clone(),
+                env.message.sender.clone(),
+                l.stake,
+                l.deadline,
+                &env,
+                vec![invite.locator, loc_b],
+            );
+            l.settlement = Settlement::Matched;
+            l.save(&mut deps.storage);
+            let mut joiner = Locator::new(
+                loc_b,
+                l.game,
+                env.message.sender.clone(),
+                l.stake,
+                l.deadline,
+            );
+            joiner.settlement = Settlement::Matched;
+            joiner.series = l.series;
+            joiner.create(&mut deps.storage)?;
+            invite.used = true;
+            invite.save(&mut deps.storage);
+            if let Some(games) = l.series {
+                // stakes are held by the series and paid out when it ends
+                let series = Series::new(l.game, l.player, env.message.sender, games, l.stake);
+                series.save(&mut deps.storage);
+                game.stake = Uint128(0);
+                game.series = Some(l.game);
+            }
             game.save(&mut deps.storage);
+            push_index(&mut deps.storage, GAME_INDEX, l.game)?;
+            let stake = l.stake;
+            update_stats(&mut deps.storage, |s| {
+                s.stake(stake);
+                s.games_created += 1;
+                s.private_games += 1;
+            })?;
+            true
+        }
+    };
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::PrivateGame {
+            game_started,
+            player1: !game_started,
+        },
+    )
+}
+
+pub fn offer_rematch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    new_locator: String,
+) -> StdResult<HandleResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let new_bytes = decode_hex32(&new_locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let mut game = Game::load(&deps.storage, locator.game)?;
+    if !game.game_over {
+        return Err(StdError::generic_err("forbidden game not over"));
+    }
+    if game.series.is_some() {
+        return Err(StdError::generic_err("forbidden series game"));
+    }
+    if game.house {
+        return Err(StdError::generic_err("forbidden house game"));
+    }
+    if env.message.sender != game.player1 && env.message.sender != game.player2 {
+        return Err(StdError::generic_err("You are not a player"));
+    }
+    if let Some(rematch) = &game.rematch {
+        // an offer canceled through claim_inactivity can be made again
+        if Locator::load(&deps.storage, rematch.locator)?.settlement != Settlement::Refunded {
+            return Err(StdError::generic_err("forbidden rematch already offered"));
         }
     }
-    Ok(HandleResponse::default())
+    if Locator::may_load(&deps.storage, new_bytes)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
+    }
+    check_funds(&env, game.stake)?;
+
+    let game_id = next_game_id(&mut deps.storage)?;
+    Locator::new(
+        new_bytes,
+        game_id,
+        env.message.sender.clone(),
+        game.stake,
+        game.deadline,
+    )
+    .create(&mut deps.storage)?;
+    update_stats(&mut deps.storage, |s| s.stake(game.stake))?;
+    let player1 = env.message.sender == game.player1;
+    game.rematch = Some(Rematch {
+        offered_by: env.message.sender,
+        locator: new_bytes,
+        started: false,
+    });
+    game.save(&mut deps.storage);
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::OfferRematch { player1 },
+    )
+}
+
+pub fn accept_rematch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    new_locator: String,
+) -> StdResult<HandleResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let new_bytes = decode_hex32(&new_locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let mut game = Game::load(&deps.storage, locator.game)?;
+    if env.message.sender != game.player1 && env.message.sender != game.player2 {
+        return Err(StdError::generic_err("You are not a player"));
+    }
+    let mut rematch = match game.rematch {
+        Some(r) => r,
+        None => return Err(StdError::generic_err("forbidden no rematch offered")),
+    };
+    if rematch.started {
+        return Err(StdError::generic_err("forbidden rematch started"));
+    }
+    if rematch.offered_by == env.message.sender {
+        return Err(StdError::generic_err("forbidden can't accept own offer"));
+    }
+    let mut offer = Locator::load(&deps.storage, rematch.locator)?;
+    if offer.settlement != Settlement::Open {
+        return Err(StdError::generic_err("forbidden game canceled"));
+    }
+    if Locator::may_load(&deps.storage, new_bytes)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
+    }
+    check_funds(&env, game.stake)?;
+
+    let player1 = env.message.sender == game.player1;
+    let mut accepted = Locator::new(
+        new_bytes,
+        offer.game,
+        env.message.sender.clone(),
+        game.stake,
+        game.deadline,
+    );
+    accepted.settlement = Settlement::Matched;
+    accepted.create(&mut deps.storage)?;
+    offer.settlement = Settlement::Matched;
+    offer.save(&mut deps.storage);
+    let rematch_game = Game::new(
+        offer.game,
+        game.player1.clone(),
+        game.player2.clone(),
+        game.stake,
+        game.deadline,
+        &env,
+        vec![rematch.locator, new_bytes],
+    );
+    rematch_game.save(&mut deps.storage);
+    push_index(&mut deps.storage, GAME_INDEX, offer.game)?;
+    update_stats(&mut deps.storage, |s| {
+        s.stake(rematch_game.stake);
+        s.games_created += 1;
+        s.private_games += 1;
+    })?;
+    rematch.started = true;
+    game.rematch = Some(rematch);
+    game.save(&mut deps.storage);
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::AcceptRematch { player1 },
+    )
 }
 
 pub fn claim_inactivity<S: Storage, A: Api, Q: Querier>(
@@ -188,31 +737,46 @@ pub fn claim_inactivity<S: Storage, A: Api, Q: Querier>(
     env: Env,
     locator: String,
 ) -> StdResult<HandleResponse> {
-    let mut bytes = [0u8; 32];
-    match hex::decode_to_slice(locator, &mut bytes as &mut [u8]) {
-        Err(_) => return Err(StdError::generic_err("bad_request invalid_locator")),
-        Ok(_) => (),
-    }
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
     let mut locator = Locator::load(&deps.storage, bytes)?;
-    if locator.canceled {
+    if locator.settlement == Settlement::Refunded {
         return Err(StdError::generic_err("forbidden game canceled"));
     }
-    let mut game;
+    if let Some(room) = Room::may_load(&deps.storage, locator.game)? {
+        return claim_room(deps, env, locator, room);
+    }
+    if let Some(team_game) = TeamGame::may_load(&deps.storage, locator.game)? {
+        return claim_team_game(deps, env, locator, team_game);
+    }
+    let game;
 
     match Game::may_load(&deps.storage, locator.game)? {
         None => {
-            if let Some(l) = lobby_game(&mut deps.storage).load()? {
+            // only the waiting player gets their stake back, and only once
+            if locator.settlement != Settlement::Open {
+                return Err(StdError::generic_err("forbidden stake already settled"));
+            }
+            if env.message.sender != locator.player {
+                return Err(StdError::generic_err("You are not a player"));
+            }
+            let free_play = locator.stake.is_zero();
+            if let Some(l) = lobby_game(&mut deps.storage, free_play).load()? {
                 if l == bytes {
-                    lobby_game(&mut deps.storage).save(&None)?;
+                    lobby_game(&mut deps.storage, free_play).save(&None)?;
                 }
             }
-            locator.canceled = true;
-            locator.save(&mut deps.storage);
-            return Ok(payout(
-                env.contract.address,
-                env.message.sender,
-                Uint128(FUNDING_AMOUNT),
-            ));
+            locator.refund(&mut deps.storage)?;
+            update_stats(&mut deps.storage, |s| {
+                s.pay_out(locator.stake);
+                s.lobby_cancellations += 1;
+            })?;
+            return answer(
+                payout(env.contract.address, locator.player, locator.stake),
+                HandleAnswer::ClaimInactivity {
+                    refunded: true,
+                    game_over: false,
+                },
+            );
         }
         Some(g) => game = g,
     }
@@ -220,178 +784,4305 @@ pub fn claim_inactivity<S: Storage, A: Api, Q: Querier>(
     if game.game_over {
         return Err(StdError::generic_err("game_over"));
     }
-    if env.block.height < game.last_play_height + PLAYER_DEADLINE_BLOCKS {
-        return Err(StdError::generic_err(
-            "under deadline for claiming inactivity",
-        ));
+    if game.abandoned(game.deadline.now(&env)) {
+        // anyone can end a game both players walked away from, bettors included
+        let res = abort_game(&mut deps.storage, &env, locator.game, game)?;
+        return answer(
+            res,
+            HandleAnswer::ClaimInactivity {
+                refunded: true,
+                game_over: true,
+            },
+        );
     }
-    if (env.message.sender == game.player1 && !game.player1_handsign.is_none())
-        || (env.message.sender == game.player2 && !game.player2_handsign.is_none())
-    {
-        game.game_over = true;
-        game.save(&mut deps.storage);
-
-        return Ok(payout(
-            env.contract.address,
-            env.message.sender,
-            Uint128(FUNDING_AMOUNT * 2),
-        ));
-    } else {
+    let player = acting_player(&deps.storage, &env, locator.game, &game)?;
+    let opponent_deadline = game.claim_deadline(&player);
+    if let Some(deadline) = opponent_deadline {
+        if game.deadline.now(&env) < deadline {
+            return Err(StdError::generic_err(
+                "under deadline for claiming inactivity",
+            ));
+        }
+    }
+    if opponent_deadline.is_none() {
         return Err(StdError::generic_err("unable to claim inactivity"));
     }
+    let res = end_game(
+        &mut deps.storage,
+        &env,
+        locator.game,
+        game,
+        player,
+        EndReason::Inactivity,
+    )?;
+    let game_over = Game::load(&deps.storage, locator.game)?.game_over;
+    answer(
+        res,
+        HandleAnswer::ClaimInactivity {
+            refunded: false,
+            game_over,
+        },
+    )
 }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GameLobby { locator } => to_binary(&game_lobby(deps, locator)?),
-        QueryMsg::GameStatus { locator } => to_binary(&game_status(deps, locator)?),
+pub fn open_room<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    room: String,
+    size: u8,
+    stake: Option<Uint128>,
+    deadline: Option<DeadlinePolicy>,
+) -> StdResult<HandleResponse> {
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let code = decode_hex32(&room, "bad_request invalid_room")?;
+    if code == loc_b {
+        return Err(StdError::generic_err(
+            "bad_request room must differ from locator",
+        ));
+    }
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
+    }
+    if may_load_room_code(&deps.storage, code)?.is_some() {
+        return Err(StdError::generic_err("bad_request room taken"));
     }
+    if !(MIN_ROOM_PLAYERS..=MAX_ROOM_PLAYERS).contains(&size) {
+        return Err(StdError::generic_err(format!(
+            "bad_request room size must be from {} to {}",
+            MIN_ROOM_PLAYERS, MAX_ROOM_PLAYERS
+        )));
+    }
+    let stake = stake.unwrap_or(Uint128(FUNDING_AMOUNT));
+    let deadline = deadline.unwrap_or_default();
+    deadline.validate()?;
+    check_funds(&env, stake)?;
+
+    let room_id = next_game_id(&mut deps.storage)?;
+    let mut room = Room::new(room_id, size, stake, deadline);
+    room.seat(env.message.sender.clone(), loc_b, deadline.now(&env))?;
+    room.save(&mut deps.storage);
+    save_room_code(&mut deps.storage, code, room_id);
+    Locator::new(loc_b, room_id, env.message.sender, stake, deadline).create(&mut deps.storage)?;
+    update_stats(&mut deps.storage, |s| s.stake(stake))?;
+    answer(HandleResponse::default(), HandleAnswer::OpenRoom { size })
 }
 
-fn game_lobby<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
+pub fn join_room<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
     locator: String,
-) -> StdResult<GameLobbyResponse> {
-    let mut bytes = [0u8; 32];
-    match hex::decode_to_slice(locator, &mut bytes as &mut [u8]) {
-        Err(_) => return Err(StdError::generic_err("bad_request invalid_locator")),
-        Ok(_) => (),
+    room: String,
+) -> StdResult<HandleResponse> {
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let code = decode_hex32(&room, "bad_request invalid_room")?;
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
     }
-    let locator = Locator::load(&deps.storage, bytes)?;
-    match Game::may_load(&deps.storage, locator.game)? {
-        None => Ok(GameLobbyResponse {
-            game_started: false,
-            player1_locator: false,
-        }),
-        Some(g) => Ok(GameLobbyResponse {
-            game_started: true,
-            player1_locator: locator.player == g.player1,
-        }),
+    let room_id = match may_load_room_code(&deps.storage, code)? {
+        Some(id) => id,
+        None => return Err(StdError::generic_err("bad_request invalid_room")),
+    };
+    let mut room = Room::load(&deps.storage, room_id)?;
+    check_funds(&env, room.stake)?;
+    room.seat(env.message.sender.clone(), loc_b, room.deadline.now(&env))?;
+    Locator::new(
+        loc_b,
+        room_id,
+        env.message.sender,
+        room.stake,
+        room.deadline,
+    )
+    .create(&mut deps.storage)?;
+    if room.started {
+        // every seat's stake is held until the last player standing is paid
+        for player in &room.players {
+            let mut locator = Locator::load(&deps.storage, player.locator)?;
+            locator.settlement = Settlement::Matched;
+            locator.save(&mut deps.storage);
+        }
+        push_index(&mut deps.storage, GAME_INDEX, room_id)?;
     }
+    room.save(&mut deps.storage);
+    let stake = room.stake;
+    let started = room.started;
+    update_stats(&mut deps.storage, |s| {
+        s.stake(stake);
+        if started {
+            s.games_created += 1;
+            s.room_games += 1;
+        }
+    })?;
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::JoinRoom {
+            started,
+            players: room.players.len() as u8,
+        },
+    )
 }
 
-fn game_status<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    locator: String,
-) -> StdResult<GameStatusResponse> {
-    let mut bytes = [0u8; 32];
-    match hex::decode_to_slice(locator, &mut bytes as &mut [u8]) {
-        Err(_) => return Err(StdError::generic_err("bad_request invalid_locator")),
-        Ok(_) => (),
+fn play_room<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    room_id: [u8; 32],
+    mut room: Room,
+    handsign: Handsign,
+) -> StdResult<HandleResponse> {
+    if room.outcome.is_some() {
+        return Err(StdError::generic_err("game_over"));
     }
-    let locator = Locator::load(&deps.storage, bytes)?;
-    let game = Game::load(&deps.storage, locator.game)?;
-    return Ok(GameStatusResponse {
-        round: game.round,
-        player1_played: !game.player1_handsign.is_none(),
-        player2_played: !game.player2_handsign.is_none(),
-        player1_wins: game.player1_wins,
-        player2_wins: game.player2_wins,
-        deadline: game.last_play_height + PLAYER_DEADLINE_BLOCKS,
-        game_over: game.game_over,
-    });
+    if !room.started {
+        return Err(StdError::generic_err("forbidden room not started"));
+    }
+    let round = room.round;
+    let players: Vec<&HumanAddr> = room.players.iter().map(|p| &p.address).collect();
+    let player = seated_player(&deps.storage, &env, room_id, &players)?;
+    room.play(&player, handsign, room.deadline.now(&env))?;
+    let data = HandleAnswer::PlayRoom {
+        round_resolved: room.round != round,
+        round,
+        standing: room.standing().count() as u8,
+        eliminated: !room.standing().any(|p| p.address == player),
+        game_over: room.winner().is_some(),
+    };
+    let mut res = HandleResponse::default();
+    match room.winner() {
+        Some(winner) => {
+            let winner = winner.address.clone();
+            res = end_room(
+                &mut deps.storage,
+                &env,
+                room,
+                winner,
+                EndReason::WinsReached,
+            )?;
+        }
+        None => room.save(&mut deps.storage),
+    }
+    answer(res, data)
 }
 
-pub fn payout(contract_address: HumanAddr, player: HumanAddr, amount: Uint128) -> HandleResponse {
-    HandleResponse {
-        messages: vec![CosmosMsg::Bank(BankMsg::Send {
-            from_address: contract_address,
-            to_address: player,
-            amount: vec![Coin {
-                denom: "uscrt".to_string(),
-                amount,
-            }],
-        })],
-        log: vec![],
-        data: None,
+/**
+ * Takes a waiting player out of a room that hasn't started and refunds them,
+ * or eliminates players who ran out of time in a room that has. A room every standing
+ * player abandoned can be ended by anyone.
+ */
+fn claim_room<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mut locator: Locator,
+    mut room: Room,
+) -> StdResult<HandleResponse> {
+    if room.outcome.is_some() {
+        return Err(StdError::generic_err("game_over"));
+    }
+    if !room.started {
+        if locator.settlement != Settlement::Open {
+            return Err(StdError::generic_err("forbidden stake already settled"));
+        }
+        if env.message.sender != locator.player {
+            return Err(StdError::generic_err("You are not a player"));
+        }
+        room.unseat(&locator.player)?;
+        room.save(&mut deps.storage);
+        locator.refund(&mut deps.storage)?;
+        update_stats(&mut deps.storage, |s| {
+            s.pay_out(locator.stake);
+            s.lobby_cancellations += 1;
+        })?;
+        return answer(
+            payout(env.contract.address, locator.player, locator.stake),
+            HandleAnswer::ClaimInactivity {
+                refunded: true,
+                game_over: false,
+            },
+        );
     }
+    if room.abandoned(room.deadline.now(&env)) {
+        // anyone can end a room every standing player walked away from
+        let res = abort_room(&mut deps.storage, &env, room)?;
+        return answer(
+            res,
+            HandleAnswer::ClaimInactivity {
+                refunded: true,
+                game_over: true,
+            },
+        );
+    }
+    let players: Vec<&HumanAddr> = room.players.iter().map(|p| &p.address).collect();
+    let player = seated_player(&deps.storage, &env, locator.game, &players)?;
+    room.time_out(&player, room.deadline.now(&env))?;
+    let mut res = HandleResponse::default();
+    let game_over = room.winner().is_some();
+    match room.winner() {
+        Some(winner) => {
+            let winner = winner.address.clone();
+            res = end_room(&mut deps.storage, &env, room, winner, EndReason::Inactivity)?;
+        }
+        None => room.save(&mut deps.storage),
+    }
+    answer(
+        res,
+        HandleAnswer::ClaimInactivity {
+            refunded: false,
+            game_over,
+        },
+    )
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::{coins, from_binary};
-    fn loc(n: u8) -> String {
-        hex::encode(format!("player{} locator is 32 bytes long", n))
+pub fn open_team_game<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    room: String,
+    team: Team,
+    stake: Option<Uint128>,
+    deadline: Option<DeadlinePolicy>,
+) -> StdResult<HandleResponse> {
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let code = decode_hex32(&room, "bad_request invalid_room")?;
+    if code == loc_b {
+        return Err(StdError::generic_err(
+            "bad_request room must differ from locator",
+        ));
     }
-
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
-        let env = mock_env("creator", &[]);
-        let msg = InitMsg {};
-
-        let res = init(&mut deps, env, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
     }
+    if may_load_room_code(&deps.storage, code)?.is_some() {
+        return Err(StdError::generic_err("bad_request room taken"));
+    }
+    let stake = stake.unwrap_or(Uint128(FUNDING_AMOUNT));
+    let deadline = deadline.unwrap_or_default();
+    deadline.validate()?;
+    check_funds(&env, stake)?;
 
-    #[test]
-    fn player_wins_round() {
-        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
-        let env = mock_env("creator", &[]);
-        let msg = InitMsg {};
-        let _res = init(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+    let game_id = next_game_id(&mut deps.storage)?;
+    let mut game = TeamGame::new(game_id, stake, deadline);
+    game.seat(env.message.sender.clone(), loc_b, team, deadline.now(&env))?;
+    game.save(&mut deps.storage);
+    save_room_code(&mut deps.storage, code, game_id);
+    Locator::new(loc_b, game_id, env.message.sender, stake, deadline).create(&mut deps.storage)?;
+    update_stats(&mut deps.storage, |s| s.stake(stake))?;
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::OpenTeamGame { started: false },
+    )
+}
 
-        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(2) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+pub fn join_team_game<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    room: String,
+    team: Team,
+) -> StdResult<HandleResponse> {
+    let loc_b = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let code = decode_hex32(&room, "bad_request invalid_room")?;
+    if Locator::may_load(&deps.storage, loc_b)?.is_some() {
+        return Err(StdError::generic_err("bad_request locator taken"));
+    }
+    let game_id = match may_load_room_code(&deps.storage, code)? {
+        Some(id) => id,
+        None => return Err(StdError::generic_err("bad_request invalid_room")),
+    };
+    let mut game = TeamGame::load(&deps.storage, game_id)?;
+    check_funds(&env, game.stake)?;
+    game.seat(
+        env.message.sender.clone(),
+        loc_b,
+        team,
+        game.deadline.now(&env),
+    )?;
+    Locator::new(
+        loc_b,
+        game_id,
+        env.message.sender,
+        game.stake,
+        game.deadline,
+    )
+    .create(&mut deps.storage)?;
+    if game.started {
+        for player in game.players() {
+            let mut locator = Locator::load(&deps.storage, player.locator)?;
+            locator.settlement = Settlement::Matched;
+            locator.save(&mut deps.storage);
+        }
+    }
+    game.save(&mut deps.storage);
+    let stake = game.stake;
+    let started = game.started;
+    update_stats(&mut deps.storage, |s| {
+        s.stake(stake);
+        if started {
+            s.games_created += 1;
+            s.team_games += 1;
+        }
+    })?;
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::JoinTeamGame { started },
+    )
+}
 
-        let env = mock_env("player1", &coins(1000, "token"));
-        let msg = HandleMsg::PlayHand {
+fn play_team_game<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    game_id: [u8; 32],
+    mut game: TeamGame,
+    handsign: Handsign,
+) -> StdResult<HandleResponse> {
+    if game.outcome.is_some() {
+        return Err(StdError::generic_err("game_over"));
+    }
+    if !game.started {
+        return Err(StdError::generic_err("forbidden game not started"));
+    }
+    let round = game.round;
+    let players: Vec<&HumanAddr> = game.players().map(|p| &p.address).collect();
+    let player = seated_player(&deps.storage, &env, game_id, &players)?;
+    game.play(&player, handsign, game.deadline.now(&env))?;
+    let (team_wins, other_team_wins) = match game.team(Team::A).iter().any(|p| p.address == player)
+    {
+        true => (game.team_a_wins, game.team_b_wins),
+        false => (game.team_b_wins, game.team_a_wins),
+    };
+    let data = HandleAnswer::PlayTeamGame {
+        round_resolved: game.round != round,
+        round,
+        team_wins,
+        other_team_wins,
+        game_over: game.winner().is_some(),
+    };
+    let mut res = HandleResponse::default();
+    match game.winner() {
+        Some(winner) => {
+            res = end_team_game(
+                &mut deps.storage,
+                &env,
+                game,
+                winner,
+                EndReason::WinsReached,
+            )?;
+        }
+        None => game.save(&mut deps.storage),
+    }
+    answer(res, data)
+}
+
+/**
+ * Takes a waiting player out of a team game that hasn't started and refunds them,
+ * or forfeits the duel of a counterpart who ran out of time.
+ */
+fn claim_team_game<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mut locator: Locator,
+    mut game: TeamGame,
+) -> StdResult<HandleResponse> {
+    if game.outcome.is_some() {
+        return Err(StdError::generic_err("game_over"));
+    }
+    if !game.started {
+        if locator.settlement != Settlement::Open {
+            return Err(StdError::generic_err("forbidden stake already settled"));
+        }
+        if env.message.sender != locator.player {
+            return Err(StdError::generic_err("You are not a player"));
+        }
+        game.unseat(&locator.player)?;
+        game.save(&mut deps.storage);
+        locator.refund(&mut deps.storage)?;
+        update_stats(&mut deps.storage, |s| {
+            s.pay_out(locator.stake);
+            s.lobby_cancellations += 1;
+        })?;
+        return answer(
+            payout(env.contract.address, locator.player, locator.stake),
+            HandleAnswer::ClaimInactivity {
+                refunded: true,
+                game_over: false,
+            },
+        );
+    }
+    if game.abandoned(game.deadline.now(&env)) {
+        // anyone can end a game all four players walked away from
+        let res = abort_team_game(&mut deps.storage, &env, game)?;
+        return answer(
+            res,
+            HandleAnswer::ClaimInactivity {
+                refunded: true,
+                game_over: true,
+            },
+        );
+    }
+    let players: Vec<&HumanAddr> = game.players().map(|p| &p.address).collect();
+    let player = seated_player(&deps.storage, &env, locator.game, &players)?;
+    game.time_out(&player, game.deadline.now(&env))?;
+    let mut res = HandleResponse::default();
+    let game_over = game.winner().is_some();
+    match game.winner() {
+        Some(winner) => {
+            res = end_team_game(&mut deps.storage, &env, game, winner, EndReason::Inactivity)?;
+        }
+        None => game.save(&mut deps.storage),
+    }
+    answer(
+        res,
+        HandleAnswer::ClaimInactivity {
+            refunded: false,
+            game_over,
+        },
+    )
+}
+
+/**
+ * Claims each locator on its own so that one failed claim doesn't abort the others.
+ * Claims check everything before writing, a failed claim leaves no changes behind.
+ */
+pub fn claim_many<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locators: Vec<String>,
+) -> StdResult<HandleResponse> {
+    if locators.len() > MAX_CLAIMS_PER_BATCH {
+        return Err(StdError::generic_err(format!(
+            "bad_request at most {} locators per claim",
+            MAX_CLAIMS_PER_BATCH
+        )));
+    }
+    let mut totals: Vec<(HumanAddr, u128)> = vec![];
+    let mut results = vec![];
+    for locator in locators {
+        match claim_inactivity(deps, env.clone(), locator.clone()) {
+            Ok(res) => {
+                for msg in res.messages {
+                    if let CosmosMsg::Bank(BankMsg::Send {
+                        to_address, amount, ..
+                    }) = msg
+                    {
+                        let sent: u128 = amount.iter().map(|c| c.amount.u128()).sum();
+                        match totals.iter_mut().find(|(a, _)| *a == to_address) {
+                            Some((_, total)) => *total += sent,
+                            None => totals.push((to_address, sent)),
+                        }
+                    }
+                }
+                results.push(ClaimResult {
+                    locator,
+                    success: true,
+                    error: None,
+                });
+            }
+            Err(e) => results.push(ClaimResult {
+                locator,
+                success: false,
+                error: Some(match e {
+                    StdError::GenericErr { msg, .. } => msg,
+                    e => e.to_string(),
+                }),
+            }),
+        }
+    }
+    Ok(HandleResponse {
+        messages: totals
+            .into_iter()
+            .map(|(to, amount)| send(env.contract.address.clone(), to, Uint128(amount)))
+            .collect(),
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::ClaimMany { results })?),
+    })
+}
+
+pub fn authorize_session_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    session_addr: HumanAddr,
+    expires_at: u64,
+) -> StdResult<HandleResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let is_player = match Game::may_load(&deps.storage, locator.game)? {
+        Some(game) => {
+            if session_addr == game.player1 || session_addr == game.player2 {
+                return Err(StdError::generic_err("bad_request session key is a player"));
+            }
+            env.message.sender == game.player1 || env.message.sender == game.player2
+        }
+        None => env.message.sender == locator.player,
+    };
+    if !is_player {
+        return Err(StdError::generic_err("You are not a player"));
+    }
+    if session_addr == env.message.sender {
+        return Err(StdError::generic_err("bad_request session key is a player"));
+    }
+    SessionKey {
+        address: session_addr,
+        expires_at,
+    }
+    .save(&mut deps.storage, locator.game, &env.message.sender);
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::AuthorizeSessionKey {
+            active: env.block.height < expires_at,
+        },
+    )
+}
+
+/// Revokes the sender's permits with the name, queries with them fail from now on
+pub fn revoke_permit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    permit_name: String,
+) -> StdResult<HandleResponse> {
+    permit::revoke(&mut deps.storage, &env.message.sender, &permit_name);
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::RevokePermit { permit_name },
+    )
+}
+
+pub fn place_bet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locator: String,
+    side: BetSide,
+) -> StdResult<HandleResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let game = Game::load(&deps.storage, locator.game)?;
+    if game.game_over {
+        return Err(StdError::generic_err("game_over"));
+    }
+    if !betting_open(&deps.storage, &game)? {
+        return Err(StdError::generic_err("forbidden betting closed"));
+    }
+    if env.message.sender == game.player1 || env.message.sender == game.player2 {
+        return Err(StdError::generic_err("forbidden players can't bet"));
+    }
+    let amount = match env.message.sent_funds.first() {
+        Some(funds) if funds.denom == FUNDING_DENOM && !funds.amount.is_zero() => funds.amount,
+        _ => {
+            return Err(StdError::generic_err(format!(
+                "insufficient_funds bet in {} required",
+                FUNDING_DENOM
+            )))
+        }
+    };
+    let mut pool = BetPool::load(&deps.storage, locator.game)?;
+    pool.place(env.message.sender, side, amount)?;
+    pool.save(&mut deps.storage);
+    answer(
+        HandleResponse::default(),
+        HandleAnswer::PlaceBet {
+            side_total: pool.total(side),
+        },
+    )
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GameLobby { locator } => to_binary(&game_lobby(deps, locator)?),
+        QueryMsg::GameStatus { locator } => to_binary(&game_status(deps, locator)?),
+        QueryMsg::Bets { locator } => to_binary(&bets(deps, locator)?),
+        QueryMsg::MyGameStatus {
+            locator,
+            height,
+            time,
+        } => to_binary(&my_game_status(deps, locator, height, time)?),
+        QueryMsg::SeriesStatus { locator } => to_binary(&series_status(deps, locator)?),
+        QueryMsg::ListGames {
+            start_after,
+            limit,
+            status_filter,
+        } => to_binary(&list_games(deps, start_after, limit, status_filter)?),
+        QueryMsg::ListOpenPrivateGames { start_after, limit } => {
+            to_binary(&list_open_private_games(deps, start_after, limit)?)
+        }
+        QueryMsg::ContractStats {} => to_binary(&load_stats(&deps.storage)?),
+        QueryMsg::House {} => to_binary(&house(deps)?),
+        QueryMsg::RoomStatus { locator } => to_binary(&room_status(deps, locator)?),
+        QueryMsg::TeamGameStatus { locator } => to_binary(&team_game_status(deps, locator)?),
+        QueryMsg::Claimability {
+            locator,
+            address,
+            height,
+            time,
+        } => to_binary(&claimability(deps, locator, address, height, time)?),
+        QueryMsg::WithPermit { permit, query } => permit_query(deps, permit, query),
+    }
+}
+
+fn game_lobby<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+) -> StdResult<GameLobbyResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    check_two_player(&deps.storage, &locator, "game_lobby")?;
+    match Game::may_load(&deps.storage, locator.game)? {
+        None => Ok(GameLobbyResponse {
+            game_started: false,
+            player1_locator: false,
+            stake: locator.stake,
+        }),
+        Some(g) => Ok(GameLobbyResponse {
+            game_started: true,
+            player1_locator: locator.player == g.player1,
+            stake: g.stake,
+        }),
+    }
+}
+
+fn game_status<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+) -> StdResult<GameStatusResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let game = Game::load(&deps.storage, locator.game)?;
+    Ok(game_status_response(game))
+}
+
+fn game_status_response(game: Game) -> GameStatusResponse {
+    GameStatusResponse {
+        round: game.round,
+        player1_played: !game.player1_handsign.is_none(),
+        player2_played: game.player2_played(),
+        player1_wins: game.player1_wins,
+        player2_wins: game.player2_wins,
+        deadline: game.deadline(),
+        deadline_unit: game.deadline.unit,
+        round_start: game.round_start,
+        player1_time_bank: game.player1_time_bank,
+        player2_time_bank: game.player2_time_bank,
+        player1_deadline: game.player1_deadline(),
+        player2_deadline: game.player2_deadline(),
+        game_over: game.game_over,
+        stake: game.stake,
+        outcome: game.outcome,
+        rematch: game.rematch.map(|r| RematchStatus {
+            offered_by: r.offered_by,
+            started: r.started,
+        }),
+    }
+}
+
+fn series_status<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+) -> StdResult<SeriesStatusResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let game = Game::load(&deps.storage, locator.game)?;
+    let series = match game.series {
+        Some(id) => Series::load(&deps.storage, id)?,
+        None => return Err(StdError::generic_err("bad_request not a series game")),
+    };
+    Ok(SeriesStatusResponse {
+        games: series.games,
+        player1_wins: series.player1_wins,
+        player2_wins: series.player2_wins,
+        stake: series.stake,
+        results: series.results,
+        outcome: series.outcome,
+        current_game: game_status_response(game),
+    })
+}
+
+fn my_game_status<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+    height: Option<u64>,
+    time: Option<u64>,
+) -> StdResult<MyGameStatusResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    check_two_player(&deps.storage, &locator, "my_game_status")?;
+    let game = match Game::may_load(&deps.storage, locator.game)? {
+        None => {
+            let canceled = locator.settlement == Settlement::Refunded;
+            let phase = match canceled {
+                true => GamePhase::Canceled,
+                false => GamePhase::WaitingForOpponentJoin,
+            };
+            return Ok(MyGameStatusResponse {
+                phase,
+                round: 1,
+                me: PlayerStatus {
+                    // waiting player can leave the lobby any time
+                    can_claim_inactivity: !canceled,
+                    ..PlayerStatus::default()
+                },
+                opponent: PlayerStatus::default(),
+                opponent_address: None,
+                deadline_unit: locator.deadline.unit,
+                stake: locator.stake,
+                outcome: None,
+            });
+        }
+        Some(game) => game,
+    };
+    let now = match game.deadline.unit {
+        DeadlineUnit::Blocks => height,
+        DeadlineUnit::Seconds => time,
+    };
+    // either player can abort a game both walked away from
+    let abandoned = !game.game_over && now.map_or(false, |now| game.abandoned(now));
+    let player1 = PlayerStatus {
+        wins: game.player1_wins,
+        played: !game.player1_handsign.is_none(),
+        can_play: !game.game_over && game.player1_handsign.is_none(),
+        can_claim_inactivity: abandoned
+            || !game.game_over
+                && match (game.player2_deadline(), now) {
+                    (Some(deadline), Some(now)) => {
+                        !game.player1_handsign.is_none() && now >= deadline
+                    }
+                    _ => false,
+                },
+        time_bank: game.player1_time_bank,
+        deadline: game.player1_deadline(),
+    };
+    let player2 = PlayerStatus {
+        wins: game.player2_wins,
+        played: game.player2_played(),
+        can_play: !game.game_over && !game.player2_played(),
+        can_claim_inactivity: abandoned
+            || !game.game_over
+                && match (game.player1_deadline(), now) {
+                    (Some(deadline), Some(now)) => game.player2_played() && now >= deadline,
+                    _ => false,
+                },
+        time_bank: game.player2_time_bank,
+        deadline: game.player2_deadline(),
+    };
+    let (me, opponent, opponent_address) = if locator.player == game.player1 {
+        (player1, player2, game.player2.clone())
+    } else {
+        (player2, player1, game.player1.clone())
+    };
+    let phase = if game.game_over {
+        GamePhase::Finished
+    } else if abandoned {
+        GamePhase::Abandoned
+    } else if !me.played {
+        GamePhase::WaitingForMe
+    } else if me.can_claim_inactivity {
+        GamePhase::Claimable
+    } else {
+        GamePhase::WaitingForOpponent
+    };
+    Ok(MyGameStatusResponse {
+        phase,
+        round: game.round,
+        me,
+        opponent,
+        opponent_address: Some(opponent_address),
+        deadline_unit: game.deadline.unit,
+        stake: game.stake,
+        outcome: game.outcome,
+    })
+}
+
+fn claimability<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+    address: HumanAddr,
+    height: Option<u64>,
+    time: Option<u64>,
+) -> StdResult<ClaimabilityResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    check_two_player(&deps.storage, &locator, "claimability")?;
+    let mut res = ClaimabilityResponse {
+        can_claim: false,
+        claimable_from: None,
+        deadline_unit: locator.deadline.unit,
+        payout: Uint128(0),
+        abort: false,
+    };
+    let game = match Game::may_load(&deps.storage, locator.game)? {
+        None => {
+            // waiting player can leave the lobby any time
+            if locator.settlement == Settlement::Open && address == locator.player {
+                res.can_claim = true;
+                res.claimable_from = Some(0);
+                res.payout = locator.stake;
+            }
+            return Ok(res);
+        }
+        Some(game) => game,
+    };
+    let player = if address == game.player1 || address == game.player2 {
+        Some(address)
+    } else if game.house && address == load_admin(&deps.storage)? {
+        // admin claims for the house
+        Some(game.player2.clone())
+    } else {
+        let players = [&game.player1, &game.player2];
+        session_player(&deps.storage, &address, height, locator.game, &players)?
+    };
+    let now = match game.deadline.unit {
+        DeadlineUnit::Blocks => height,
+        DeadlineUnit::Seconds => time,
+    };
+    let stake = match game.series {
+        Some(series) => Series::load(&deps.storage, series)?.stake,
+        None => game.stake,
+    };
+    if let (false, Some(deadline)) = (game.game_over, game.abandon_deadline()) {
+        // anyone can abort a game both players walked away from, players get their stake back
+        res.can_claim = now.map_or(false, |now| now >= deadline);
+        res.claimable_from = Some(deadline);
+        res.abort = true;
+        if player.is_some() {
+            res.payout = stake;
+        }
+        return Ok(res);
+    }
+    let player = match player {
+        Some(player) => player,
+        None => return Err(StdError::generic_err("You are not a player")),
+    };
+    if game.game_over {
+        return Ok(res);
+    }
+    if let Some(deadline) = game.claim_deadline(&player) {
+        // claiming inactivity decides the whole series
+        res.can_claim = now.map_or(false, |now| now >= deadline);
+        res.claimable_from = Some(deadline);
+        res.payout = Uint128(stake.u128() * 2);
+    }
+    Ok(res)
+}
+
+fn house<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<HouseResponse> {
+    let house = House::load(&deps.storage)?;
+    Ok(HouseResponse {
+        bankroll: house.bankroll,
+        exposure_limit: Uint128(MAX_HOUSE_EXPOSURE_PER_BLOCK),
+        exposure: house.exposure,
+        exposure_height: house.exposure_height,
+    })
+}
+
+fn room_status<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+) -> StdResult<RoomStatusResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let room = Room::load(&deps.storage, locator.game)?;
+    Ok(RoomStatusResponse {
+        size: room.size,
+        started: room.started,
+        round: room.round,
+        players: room
+            .players
+            .iter()
+            .map(|p| RoomPlayerStatus {
+                address: p.address.clone(),
+                played: p.handsign.is_some(),
+                eliminated: p.eliminated,
+                time_bank: p.time_bank,
+                deadline: room.player_deadline(p),
+            })
+            .collect(),
+        deadline_unit: room.deadline.unit,
+        round_start: room.round_start,
+        stake: room.stake,
+        game_over: room.outcome.is_some(),
+        outcome: room.outcome.clone(),
+    })
+}
+
+fn team_game_status<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+) -> StdResult<TeamGameStatusResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let game = TeamGame::load(&deps.storage, locator.game)?;
+    let team_status = |team: Team| -> Vec<TeamPlayerStatus> {
+        game.team(team)
+            .iter()
+            .enumerate()
+            .map(|(duel, p)| TeamPlayerStatus {
+                address: p.address.clone(),
+                played: p.handsign.is_some(),
+                time_bank: p.time_bank,
+                deadline: match game.started {
+                    true => game.player_deadline(team, duel),
+                    false => None,
+                },
+            })
+            .collect()
+    };
+    Ok(TeamGameStatusResponse {
+        started: game.started,
+        round: game.round,
+        team_a: team_status(Team::A),
+        team_b: team_status(Team::B),
+        team_a_wins: game.team_a_wins,
+        team_b_wins: game.team_b_wins,
+        deadline_unit: game.deadline.unit,
+        round_start: game.round_start,
+        stake: game.stake,
+        game_over: game.outcome.is_some(),
+        outcome: game.outcome.clone(),
+    })
+}
+
+fn list_games<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    status_filter: Option<GameFilter>,
+) -> StdResult<ListGamesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT) as usize;
+    let total = index_len(&deps.storage, GAME_INDEX)?;
+    let start = start_after.map_or(0, |pos| pos + 1);
+    let mut games = vec![];
+    let mut last_scanned = None;
+    for pos in start..total.min(start + MAX_LIST_SCAN) {
+        if games.len() == limit {
+            break;
+        }
+        last_scanned = Some(pos);
+        let id = index_get(&deps.storage, GAME_INDEX, pos)?;
+        let summary = match Room::may_load(&deps.storage, id)? {
+            Some(room) => GameSummary {
+                index: pos,
+                id: public_id(&id),
+                round: room.round,
+                players: room.size,
+                player1_wins: 0,
+                player2_wins: 0,
+                deadline: room.deadline(),
+                deadline_unit: room.deadline.unit,
+                stake: room.stake,
+                game_over: room.outcome.is_some(),
+            },
+            None => {
+                let game = Game::load(&deps.storage, id)?;
+                GameSummary {
+                    index: pos,
+                    id: public_id(&id),
+                    round: game.round.into(),
+                    players: 2,
+                    player1_wins: game.player1_wins,
+                    player2_wins: game.player2_wins,
+                    deadline: game.deadline(),
+                    deadline_unit: game.deadline.unit,
+                    stake: game.stake,
+                    game_over: game.game_over,
+                }
+            }
+        };
+        let matches = match status_filter {
+            None => true,
+            Some(GameFilter::Active) => !summary.game_over,
+            Some(GameFilter::Finished) => summary.game_over,
+        };
+        if matches {
+            games.push(summary);
+        }
+    }
+    Ok(ListGamesResponse {
+        games,
+        last_scanned,
+        total,
+    })
+}
+
+fn list_open_private_games<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListOpenPrivateGamesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT) as usize;
+    let total = index_len(&deps.storage, OPEN_PRIVATE_INDEX)?;
+    let start = start_after.map_or(0, |pos| pos + 1);
+    let mut games = vec![];
+    let mut last_scanned = None;
+    for pos in start..total.min(start + MAX_LIST_SCAN) {
+        if games.len() == limit {
+            break;
+        }
+        last_scanned = Some(pos);
+        let id = index_get(&deps.storage, OPEN_PRIVATE_INDEX, pos)?;
+        let invite = Invite::load(&deps.storage, id)?;
+        let locator = Locator::load(&deps.storage, invite.locator)?;
+        if invite.used || locator.settlement != Settlement::Open {
+            continue;
+        }
+        games.push(OpenPrivateGame {
+            index: pos,
+            id: public_id(&locator.game),
+            invite: hex::encode(id),
+            stake: locator.stake,
+            deadline: locator.deadline,
+            series: locator.series,
+        });
+    }
+    Ok(ListOpenPrivateGamesResponse {
+        games,
+        last_scanned,
+        total,
+    })
+}
+
+/// Answers a query about the player who signed the permit
+fn permit_query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    permit: Permit,
+    query: PermitQuery,
+) -> StdResult<Binary> {
+    let contract = load_contract_address(&deps.storage)?;
+    let player = permit::validate(&deps.storage, &permit, &contract)?;
+    match query {
+        PermitQuery::MyGames { start_after, limit } => {
+            permit::require(&permit, Permission::History)?;
+            to_binary(&my_games(deps, player, start_after, limit)?)
+        }
+        PermitQuery::MyStats {} => {
+            permit::require(&permit, Permission::Stats)?;
+            to_binary(&load_player_stats(&deps.storage, &player)?)
+        }
+    }
+}
+
+fn my_games<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    player: HumanAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<MyGamesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT) as u64;
+    let index = player_index(&player);
+    let total = index_len(&deps.storage, &index)?;
+    let start = start_after.map_or(0, |pos| pos + 1);
+    let mut games = vec![];
+    for pos in start..total.min(start + limit) {
+        let id = index_get(&deps.storage, &index, pos)?;
+        games.push(player_game(&deps.storage, pos, id, &player)?);
+    }
+    Ok(MyGamesResponse { games, total })
+}
+
+/// Game a player joined with the locator, whether it is a duel, a room or a team game
+fn player_game<S: Storage>(
+    storage: &S,
+    index: u64,
+    id: [u8; 32],
+    player: &HumanAddr,
+) -> StdResult<PlayerGame> {
+    let locator = Locator::load(storage, id)?;
+    let (started, result) = if let Some(room) = Room::may_load(storage, locator.game)? {
+        let result = room
+            .outcome
+            .map(|o| (o.winner.map(|w| w == *player), o.reason));
+        (room.started, result)
+    } else if let Some(game) = TeamGame::may_load(storage, locator.game)? {
+        let result = game.outcome.as_ref().map(|o| {
+            let won = o
+                .winner
+                .map(|team| game.team(team).iter().any(|p| p.address == *player));
+            (won, o.reason)
+        });
+        (game.started, result)
+    } else if let Some(game) = Game::may_load(storage, locator.game)? {
+        let result = match (game.game_over, game.outcome) {
+            (true, Some(o)) => Some((o.winner.map(|w| w == *player), o.reason)),
+            _ => None,
+        };
+        (true, result)
+    } else {
+        (false, None)
+    };
+    Ok(PlayerGame {
+        index,
+        locator: hex::encode(id),
+        stake: locator.stake,
+        started,
+        game_over: result.is_some(),
+        refunded: locator.settlement == Settlement::Refunded,
+        won: result.and_then(|(won, _)| won),
+        reason: result.map(|(_, reason)| reason),
+    })
+}
+
+/// Rejects locators of rooms and team games, which have status queries of their own
+fn check_two_player<S: Storage>(storage: &S, locator: &Locator, query: &str) -> StdResult<()> {
+    if Room::may_load(storage, locator.game)?.is_some()
+        || TeamGame::may_load(storage, locator.game)?.is_some()
+    {
+        return Err(StdError::generic_err(format!(
+            "bad_request {} is for two player games",
+            query
+        )));
+    }
+    Ok(())
+}
+
+/// Hash of a game id for listings, keeps listed ids opaque to clients
+fn public_id(id: &[u8; 32]) -> String {
+    hex::encode(Sha256::digest(id))
+}
+
+fn bets<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    locator: String,
+) -> StdResult<BetsResponse> {
+    let bytes = decode_hex32(&locator, "bad_request invalid_locator")?;
+    let locator = Locator::load(&deps.storage, bytes)?;
+    let game = Game::load(&deps.storage, locator.game)?;
+    let pool = BetPool::load(&deps.storage, locator.game)?;
+    Ok(BetsResponse {
+        player1_total: pool.total(BetSide::Player1),
+        player2_total: pool.total(BetSide::Player2),
+        open: betting_open(&deps.storage, &game)?,
+    })
+}
+
+/// Bets are taken in the first round only, series bets close after the first game
+fn betting_open<S: Storage>(storage: &S, game: &Game) -> StdResult<bool> {
+    if game.game_over || game.round > 1 {
+        return Ok(false);
+    }
+    match game.series {
+        // series bets are settled on the series winner
+        Some(series) => Ok(Series::load(storage, series)?.results.is_empty()),
+        None => Ok(true),
+    }
+}
+
+/**
+ * Decodes a locator or code, which must be exactly 64 lowercase hex characters.
+ * Fixed width keeps messages carrying them the same size whatever the player's move.
+ */
+fn decode_hex32(input: &str, error: &str) -> StdResult<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    if input.len() != 64 || input.bytes().any(|b| b.is_ascii_uppercase()) {
+        return Err(StdError::generic_err(error));
+    }
+    match hex::decode_to_slice(input, &mut bytes as &mut [u8]) {
+        Err(_) => Err(StdError::generic_err(error)),
+        Ok(_) => Ok(bytes),
+    }
+}
+
+/// Checks that sender staked the required amount. Free games take no funds.
+fn check_funds(env: &Env, stake: Uint128) -> StdResult<()> {
+    if stake.is_zero() {
+        if env.message.sent_funds.iter().any(|c| !c.amount.is_zero()) {
+            return Err(StdError::generic_err(
+                "bad_request free game takes no funds",
+            ));
+        }
+        return Ok(());
+    }
+    match env.message.sent_funds.first() {
+        Some(funds) if funds.denom == FUNDING_DENOM && funds.amount >= stake => Ok(()),
+        _ => Err(StdError::generic_err(format!(
+            "insufficient_funds {} {} required",
+            stake, FUNDING_DENOM
+        ))),
+    }
+}
+
+/**
+ * Ends the game with a winner, pays out the stakes and settles spectator bets.
+ * A series game only pays out when it decides the series, otherwise the next game starts.
+ */
+fn end_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    game_id: [u8; 32],
+    mut game: Game,
+    winner: HumanAddr,
+    reason: EndReason,
+) -> StdResult<HandleResponse> {
+    let locators = matched_locators(storage, &game.locators)?;
+    let mut amount = game.finish(winner.clone(), reason, env.block.height);
+    if reason == EndReason::Inactivity {
+        update_stats(storage, |s| s.inactivity_claims += 1)?;
+    }
+    if let Some(series_id) = game.series {
+        let mut series = Series::load(storage, series_id)?;
+        amount = series.record(game.outcome.clone().unwrap());
+        series.save(storage);
+        if series.outcome.is_none() {
+            game.restart(game.deadline.now(env));
+            game.save(storage);
+            return Ok(HandleResponse::default());
+        }
+    }
+    game.save(storage);
+    release_stakes(storage, locators, std::slice::from_ref(&winner), amount)?;
+    let house_won = game.house && winner == game.player2;
+    update_stats(storage, |s| {
+        match house_won {
+            true => s.return_to_house(amount),
+            false => s.pay_out(amount),
+        }
+        s.games_finished += 1;
+    })?;
+
+    let side = bet_side(&game, &winner);
+    if house_won {
+        // house winnings go back to the bankroll
+        let mut house = House::load(storage)?;
+        house.bankroll += amount;
+        house.save(storage)?;
+        return Ok(HandleResponse {
+            messages: settle_bets(storage, &env.contract.address, game_id, side)?,
+            ..HandleResponse::default()
+        });
+    }
+    let mut res = payout(env.contract.address.clone(), winner, amount);
+    res.messages
+        .extend(settle_bets(storage, &env.contract.address, game_id, side)?);
+    Ok(res)
+}
+
+/// Ends a game both players abandoned, stakes and bets are returned
+fn abort_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    game_id: [u8; 32],
+    mut game: Game,
+) -> StdResult<HandleResponse> {
+    let locators = matched_locators(storage, &game.locators)?;
+    game.abort(env.block.height);
+    let stake = match game.series {
+        Some(series_id) => {
+            let mut series = Series::load(storage, series_id)?;
+            series.abort(game.outcome.clone().unwrap());
+            series.save(storage);
+            series.stake
+        }
+        None => game.stake,
+    };
+    game.save(storage);
+    for locator in &locators {
+        update_player_stats(storage, &locator.player, |s| s.total_refunded += stake)?;
+    }
+    release_stakes(storage, locators, &[], Uint128::zero())?;
+    update_stats(storage, |s| {
+        s.pay_out(stake);
+        match game.house {
+            true => s.return_to_house(stake),
+            false => s.pay_out(stake),
+        }
+        s.games_finished += 1;
+    })?;
+
+    let mut res = payout(env.contract.address.clone(), game.player1.clone(), stake);
+    if game.house {
+        let mut house = House::load(storage)?;
+        house.bankroll += stake;
+        house.save(storage)?;
+    } else {
+        res.messages
+            .extend(payout(env.contract.address.clone(), game.player2.clone(), stake).messages);
+    }
+    res.messages
+        .extend(settle_bets(storage, &env.contract.address, game_id, None)?);
+    Ok(res)
+}
+
+/// Ends the room with the last player standing taking every stake
+fn end_room<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    mut room: Room,
+    winner: HumanAddr,
+    reason: EndReason,
+) -> StdResult<HandleResponse> {
+    let ids: Vec<[u8; 32]> = room.players.iter().map(|p| p.locator).collect();
+    let locators = matched_locators(storage, &ids)?;
+    let amount = room.finish(winner.clone(), reason, env.block.height);
+    room.save(storage);
+    release_stakes(storage, locators, std::slice::from_ref(&winner), amount)?;
+    update_stats(storage, |s| {
+        s.pay_out(amount);
+        s.games_finished += 1;
+        if reason == EndReason::Inactivity {
+            s.inactivity_claims += 1;
+        }
+    })?;
+    Ok(payout(env.contract.address.clone(), winner, amount))
+}
+
+/// Ends a room every standing player abandoned, standing players get the stakes back
+fn abort_room<S: Storage>(storage: &mut S, env: &Env, mut room: Room) -> StdResult<HandleResponse> {
+    let ids: Vec<[u8; 32]> = room.players.iter().map(|p| p.locator).collect();
+    let locators = matched_locators(storage, &ids)?;
+    let payouts = room.abort(env.block.height);
+    room.save(storage);
+    release_stakes(storage, locators, &[], Uint128::zero())?;
+    let mut res = HandleResponse::default();
+    for (player, amount) in payouts {
+        update_player_stats(storage, &player, |s| s.total_refunded += amount)?;
+        update_stats(storage, |s| s.pay_out(amount))?;
+        res.messages
+            .extend(payout(env.contract.address.clone(), player, amount).messages);
+    }
+    update_stats(storage, |s| s.games_finished += 1)?;
+    Ok(res)
+}
+
+/// Ends a team game both duels of a round were abandoned in, every player gets their stake back
+fn abort_team_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    mut game: TeamGame,
+) -> StdResult<HandleResponse> {
+    let ids: Vec<[u8; 32]> = game.players().map(|p| p.locator).collect();
+    let locators = matched_locators(storage, &ids)?;
+    let stake = game.abort(env.block.height);
+    game.save(storage);
+    release_stakes(storage, locators, &[], Uint128::zero())?;
+    let mut res = HandleResponse::default();
+    for player in game.players() {
+        update_player_stats(storage, &player.address, |s| s.total_refunded += stake)?;
+        update_stats(storage, |s| s.pay_out(stake))?;
+        res.messages
+            .extend(payout(env.contract.address.clone(), player.address.clone(), stake).messages);
+    }
+    update_stats(storage, |s| s.games_finished += 1)?;
+    Ok(res)
+}
+
+/// Ends the team game with members of the winning team splitting every stake equally
+fn end_team_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    mut game: TeamGame,
+    winner: Team,
+    reason: EndReason,
+) -> StdResult<HandleResponse> {
+    let ids: Vec<[u8; 32]> = game.players().map(|p| p.locator).collect();
+    let locators = matched_locators(storage, &ids)?;
+    let share = game.finish(winner, reason, env.block.height);
+    game.save(storage);
+    let members = game.team(winner);
+    let winners: Vec<HumanAddr> = members.iter().map(|m| m.address.clone()).collect();
+    release_stakes(storage, locators, &winners, share)?;
+    let amount = Uint128(share.u128() * members.len() as u128);
+    update_stats(storage, |s| {
+        s.pay_out(amount);
+        s.games_finished += 1;
+        if reason == EndReason::Inactivity {
+            s.inactivity_claims += 1;
+        }
+    })?;
+    let mut res = HandleResponse::default();
+    for member in members {
+        res.messages
+            .extend(payout(env.contract.address.clone(), member.address.clone(), share).messages);
+    }
+    Ok(res)
+}
+
+/// Locators holding the stakes of a game that is ending, checked before anything is written
+fn matched_locators<S: Storage>(storage: &S, ids: &[[u8; 32]]) -> StdResult<Vec<Locator>> {
+    let mut locators = vec![];
+    for id in ids {
+        let locator = Locator::load(storage, *id)?;
+        if locator.settlement != Settlement::Matched {
+            return Err(StdError::generic_err("forbidden stake already settled"));
+        }
+        locators.push(locator);
+    }
+    Ok(locators)
+}
+
+/// Marks the stakes paid and records the result in the stats of each player
+fn release_stakes<S: Storage>(
+    storage: &mut S,
+    locators: Vec<Locator>,
+    winners: &[HumanAddr],
+    share: Uint128,
+) -> StdResult<()> {
+    for mut locator in locators {
+        locator.settlement = Settlement::Paid;
+        locator.save(storage);
+        let won = winners.contains(&locator.player);
+        update_player_stats(storage, &locator.player, |s| {
+            s.games_finished += 1;
+            if won {
+                s.wins += 1;
+                s.total_won += share;
+            }
+        })?;
+    }
+    Ok(())
+}
+
+/// Player the sender acts for, either the player themselves or their unexpired session key
+fn acting_player<S: Storage>(
+    storage: &S,
+    env: &Env,
+    game_id: [u8; 32],
+    game: &Game,
+) -> StdResult<HumanAddr> {
+    let sender = &env.message.sender;
+    if *sender == game.player1 || *sender == game.player2 {
+        return Ok(sender.clone());
+    }
+    if game.house && *sender == load_admin(storage)? {
+        // admin claims for the house when the player walks away
+        return Ok(game.player2.clone());
+    }
+    let players = [&game.player1, &game.player2];
+    match session_player(storage, sender, Some(env.block.height), game_id, &players)? {
+        Some(player) => Ok(player),
+        None => Err(StdError::generic_err("You are not a player")),
+    }
+}
+
+/// Player the sender acts for in a room or team game, either a seated player or their session key
+fn seated_player<S: Storage>(
+    storage: &S,
+    env: &Env,
+    game_id: [u8; 32],
+    players: &[&HumanAddr],
+) -> StdResult<HumanAddr> {
+    let sender = &env.message.sender;
+    if players.contains(&sender) {
+        return Ok(sender.clone());
+    }
+    match session_player(storage, sender, Some(env.block.height), game_id, players)? {
+        Some(player) => Ok(player),
+        None => Err(StdError::generic_err("You are not a player")),
+    }
+}
+
+/// Player the address holds an unexpired session key for, the height is needed to check expiry
+fn session_player<S: Storage>(
+    storage: &S,
+    address: &HumanAddr,
+    height: Option<u64>,
+    game_id: [u8; 32],
+    players: &[&HumanAddr],
+) -> StdResult<Option<HumanAddr>> {
+    for player in players {
+        if let Some(key) = SessionKey::may_load(storage, game_id, player)? {
+            if key.address != *address {
+                continue;
+            }
+            let height = height.ok_or_else(|| {
+                StdError::generic_err("bad_request height needed for a session key")
+            })?;
+            if height < key.expires_at {
+                return Ok(Some((*player).clone()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Picks the house's move from the secret entropy pool
+fn house_handsign<S: Storage>(storage: &mut S, input: &[u8]) -> StdResult<Handsign> {
+    Ok(match entropy::draw_below(storage, 3, input)? {
+        0 => Handsign::ROCK,
+        1 => Handsign::PAPR,
+        _ => Handsign::SCRS,
+    })
+}
+
+fn bet_side(game: &Game, player: &HumanAddr) -> Option<BetSide> {
+    if *player == game.player1 {
+        Some(BetSide::Player1)
+    } else if *player == game.player2 {
+        Some(BetSide::Player2)
+    } else {
+        None
+    }
+}
+
+/// Pays out spectator bets of a finished game, refunding everyone if there is no winner
+fn settle_bets<S: Storage>(
+    storage: &mut S,
+    contract_address: &HumanAddr,
+    game: [u8; 32],
+    winner: Option<BetSide>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut pool = match BetPool::may_load(storage, game)? {
+        None => return Ok(vec![]),
+        Some(pool) => pool,
+    };
+    let payouts = pool.settle(winner);
+    pool.save(storage);
+    Ok(payouts
+        .into_iter()
+        .map(|(bettor, amount)| send(contract_address.clone(), bettor, amount))
+        .collect())
+}
+
+/// Adds the JSON-encoded answer to the response data
+fn answer(mut res: HandleResponse, answer: HandleAnswer) -> StdResult<HandleResponse> {
+    res.data = Some(to_binary(&answer)?);
+    Ok(res)
+}
+
+fn send(contract_address: HumanAddr, to_address: HumanAddr, amount: Uint128) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        from_address: contract_address,
+        to_address,
+        amount: vec![Coin {
+            denom: "uscrt".to_string(),
+            amount,
+        }],
+    })
+}
+
+pub fn payout(contract_address: HumanAddr, player: HumanAddr, amount: Uint128) -> HandleResponse {
+    if amount.is_zero() {
+        // free games are recorded without moving funds
+        return HandleResponse::default();
+    }
+    HandleResponse {
+        messages: vec![send(contract_address, player, amount)],
+        log: vec![],
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{PLAYER_DEADLINE_BLOCKS, PLAYER_INCREMENT_BLOCKS};
+    use crate::msg::{ContractStats, PlayerStats};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{coins, from_binary, from_slice, to_vec};
+    fn loc(n: u8) -> String {
+        hex::encode(format!("player{} locator is 32 bytes long", n))
+    }
+    fn invite(n: u8) -> String {
+        hex::encode(format!("invite{} code is 32 bytes long!!!", n))
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+
+        let res = init(&mut deps, env, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    #[test]
+    fn player_wins_round() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(1000, "token"));
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(2, "token"));
+        let msg = HandleMsg::PlayHand {
+            locator: loc(2),
+            handsign: Handsign::PAPR,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(0, value.player1_wins);
+        assert_eq!(1, value.player2_wins);
+        assert_eq!(false, value.game_over);
+    }
+
+    #[test]
+    fn player_win_payout() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        for r in 0..WINS_TO_FINISH {
+            let env = mock_env("player1", &coins(1000, "token"));
+            let msg = HandleMsg::PlayHand {
+                locator: loc(1),
+                handsign: Handsign::ROCK,
+                entropy: None,
+                padding: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+
+            let env = mock_env("player2", &coins(2, "token"));
+            let msg = HandleMsg::PlayHand {
+                locator: loc(2),
+                handsign: Handsign::PAPR,
+                entropy: None,
+                padding: None,
+            };
+            let res = handle(&mut deps, env, msg).unwrap();
+            if r == WINS_TO_FINISH - 1 {
+                assert_eq!(res.messages.len(), 1);
+                match &res.messages[0] {
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address, amount, ..
+                    }) => {
+                        assert_eq!(to_address.as_str(), "player2");
+                        assert_eq!(amount.len(), 1);
+                        assert_eq!(amount[0].denom, "uscrt");
+                        assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 2));
+                    }
+                    _ => {
+                        panic!("Expected payout for winner");
+                    }
+                }
+            } else {
+                assert_eq!(res.messages.len(), 0);
+            }
+        }
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(0, value.player1_wins);
+        assert_eq!(WINS_TO_FINISH, value.player2_wins);
+        assert_eq!(true, value.game_over);
+        let outcome = value.outcome.unwrap();
+        assert_eq!(Some(HumanAddr::from("player2")), outcome.winner);
+        assert_eq!(EndReason::WinsReached, outcome.reason);
+        assert_eq!(Uint128(FUNDING_AMOUNT * 2), outcome.payout);
+    }
+
+    #[test]
+    fn my_game_status_perspective() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::MyGameStatus {
+            locator: loc(1),
+            height: None,
+            time: None,
+        };
+        let res = query(&deps, msg).unwrap();
+        let value: MyGameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(GamePhase::WaitingForOpponentJoin, value.phase);
+        assert_eq!(None, value.opponent_address);
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(2),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let msg = QueryMsg::MyGameStatus {
+            locator: loc(1),
+            height: Some(env.block.height),
+            time: Some(env.block.time),
+        };
+        let res = query(&deps, msg).unwrap();
+        let value: MyGameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(GamePhase::WaitingForMe, value.phase);
+        assert_eq!(Some(HumanAddr::from("player2")), value.opponent_address);
+        assert_eq!(true, value.me.can_play);
+        assert_eq!(false, value.opponent.can_play);
+        assert_eq!(true, value.opponent.played);
+
+        let msg = QueryMsg::MyGameStatus {
+            locator: loc(2),
+            height: Some(env.block.height),
+            time: Some(env.block.time),
+        };
+        let res = query(&deps, msg).unwrap();
+        let value: MyGameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(GamePhase::WaitingForOpponent, value.phase);
+        assert_eq!(Some(HumanAddr::from("player1")), value.opponent_address);
+        assert_eq!(false, value.me.can_claim_inactivity);
+
+        let msg = QueryMsg::MyGameStatus {
+            locator: loc(2),
+            height: Some(env.block.height + PLAYER_DEADLINE_BLOCKS),
+            time: None,
+        };
+        let res = query(&deps, msg).unwrap();
+        let value: MyGameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(GamePhase::Claimable, value.phase);
+        assert_eq!(true, value.me.can_claim_inactivity);
+        assert_eq!(false, value.opponent.can_claim_inactivity);
+
+        let mut env = mock_env("player2", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(2),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::MyGameStatus {
+            locator: loc(1),
+            height: None,
+            time: None,
+        };
+        let res = query(&deps, msg).unwrap();
+        let value: MyGameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(GamePhase::Finished, value.phase);
+        assert_eq!(false, value.me.can_play);
+    }
+
+    fn claimability_of<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>,
+        locator: String,
+        address: &str,
+        height: Option<u64>,
+    ) -> ClaimabilityResponse {
+        let msg = QueryMsg::Claimability {
+            locator,
+            address: HumanAddr::from(address),
+            height,
+            time: None,
+        };
+        from_binary(&query(deps, msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn claimability() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // waiting player can take their stake back right away
+        let value = claimability_of(&deps, loc(1), "player1", None);
+        assert_eq!(true, value.can_claim);
+        assert_eq!(Some(0), value.claimable_from);
+        assert_eq!(Uint128(FUNDING_AMOUNT), value.payout);
+        let value = claimability_of(&deps, loc(1), "player2", None);
+        assert_eq!(false, value.can_claim);
+
+        let mut env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        env.block.height += 3;
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+        let join_height = env.block.height;
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(2) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(join_height, value.round_start);
+
+        // nobody can claim before playing, the game can only be aborted once both run out of time
+        let deadline = join_height + PLAYER_DEADLINE_BLOCKS;
+        let value = claimability_of(&deps, loc(2), "player2", Some(deadline - 1));
+        assert_eq!(false, value.can_claim);
+        assert_eq!(Some(deadline), value.claimable_from);
+        assert_eq!(true, value.abort);
+        assert_eq!(Uint128(FUNDING_AMOUNT), value.payout);
+        let value = claimability_of(&deps, loc(1), "player1", Some(deadline));
+        assert_eq!(true, value.can_claim);
+        assert_eq!(Uint128(FUNDING_AMOUNT), value.payout);
+        // anyone can abort, only players get a refund
+        let value = claimability_of(&deps, loc(2), "stranger", Some(deadline));
+        assert_eq!(true, value.can_claim);
+        assert_eq!(true, value.abort);
+        assert_eq!(Uint128(0), value.payout);
+
+        let msg = HandleMsg::PlayHand {
+            locator: loc(2),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, mock_env("player2", &[]), msg).unwrap();
+
+        let value = claimability_of(&deps, loc(2), "player2", Some(deadline - 1));
+        assert_eq!(false, value.can_claim);
+        assert_eq!(false, value.abort);
+        assert_eq!(Some(deadline), value.claimable_from);
+        assert_eq!(DeadlineUnit::Blocks, value.deadline_unit);
+        assert_eq!(Uint128(FUNDING_AMOUNT * 2), value.payout);
+        let value = claimability_of(&deps, loc(2), "player2", None);
+        assert_eq!(false, value.can_claim);
+        let value = claimability_of(&deps, loc(2), "player2", Some(deadline));
+        assert_eq!(true, value.can_claim);
+        let value = claimability_of(&deps, loc(1), "player1", Some(deadline));
+        assert_eq!(false, value.can_claim);
+        assert_eq!(None, value.claimable_from);
+
+        let msg = QueryMsg::Claimability {
+            locator: loc(2),
+            address: HumanAddr::from("stranger"),
+            height: None,
+            time: None,
+        };
+        assert_eq!(
+            query(&deps, msg).unwrap_err(),
+            StdError::generic_err("You are not a player")
+        );
+
+        // a session key can only be checked at a known height
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::AuthorizeSessionKey {
+            locator: loc(2),
+            session_addr: HumanAddr::from("session2"),
+            expires_at: deadline + 1,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let msg = QueryMsg::Claimability {
+            locator: loc(2),
+            address: HumanAddr::from("session2"),
+            height: None,
+            time: None,
+        };
+        assert_eq!(
+            query(&deps, msg).unwrap_err(),
+            StdError::generic_err("bad_request height needed for a session key")
+        );
+        let value = claimability_of(&deps, loc(2), "session2", Some(deadline));
+        assert_eq!(true, value.can_claim);
+
+        let mut env = mock_env("player2", &[]);
+        env.block.height = deadline;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(2),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+                assert_eq!(coins(FUNDING_AMOUNT * 2, "uscrt"), *amount)
+            }
+            _ => panic!("expected payout"),
+        }
+        let value = claimability_of(&deps, loc(2), "player2", Some(deadline));
+        assert_eq!(false, value.can_claim);
+    }
+
+    #[test]
+    fn game_takes_two_players() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(3),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg);
+
+        let msg = QueryMsg::GameLobby { locator: loc(1) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.game_started);
+        assert_eq!(true, value.player1_locator);
+
+        let msg = QueryMsg::GameLobby { locator: loc(2) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.game_started);
+        assert_eq!(false, value.player1_locator);
+
+        let msg = QueryMsg::GameLobby { locator: loc(3) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.game_started);
+    }
+
+    #[test]
+    fn locator_reuse_rejected() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        for (player, n) in &[("player1", 1), ("player2", 2)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(*n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Old locator can't start a new game over the running one
+        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("bad_request locator taken")
+        );
+        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(2),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("bad_request locator taken")
+        );
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.player1_played);
+
+        // Invite seats one player only
+        for (player, n) in &[("player3", 3), ("player4", 4)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::PrivateGame {
+                locator: loc(*n),
+                invite: invite(3),
+                stake: None,
+                deadline: None,
+                series: None,
+                discoverable: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let env = mock_env("player5", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(5),
+            invite: invite(3),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("forbidden invite used")
+        );
+    }
+
+    #[test]
+    fn private_game_matching() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // JoinGame shouldn't interfere
+        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(3),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(1000, "token"));
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(2, "token"));
+        let msg = HandleMsg::PlayHand {
+            locator: loc(2),
+            handsign: Handsign::PAPR,
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(0, value.player1_wins);
+        assert_eq!(1, value.player2_wins);
+        assert_eq!(false, value.game_over);
+
+        // Each seat has its own locator and the invite doesn't locate the game
+        let res = query(&deps, QueryMsg::GameLobby { locator: loc(2) }).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.player1_locator);
+        query(&deps, QueryMsg::GameLobby { locator: invite(5) }).unwrap_err();
+    }
+
+    #[test]
+    fn list_games() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        for (player, n, discoverable) in &[("player1", 1, true), ("player2", 2, false)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::PrivateGame {
+                locator: loc(*n),
+                invite: invite(*n),
+                stake: None,
+                deadline: None,
+                series: None,
+                discoverable: *discoverable,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let res = query(
+            &deps,
+            QueryMsg::ListOpenPrivateGames {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let value: ListOpenPrivateGamesResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.games.len());
+        assert_eq!(FUNDING_AMOUNT, value.games[0].stake.u128());
+        assert_ne!(loc(1), value.games[0].id);
+        assert_eq!(invite(1), value.games[0].invite);
+
+        // Joined games are no longer open
+        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(3),
+            invite: value.games[0].invite.clone(),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let res = query(
+            &deps,
+            QueryMsg::ListOpenPrivateGames {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let value: ListOpenPrivateGamesResponse = from_binary(&res).unwrap();
+        assert_eq!(0, value.games.len());
+
+        for n in 4..7 {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        // Private game and one public game so far
+        let msg = QueryMsg::ListGames {
+            start_after: None,
+            limit: Some(1),
+            status_filter: Some(GameFilter::Active),
+        };
+        let value: ListGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(2, value.total);
+        assert_eq!(1, value.games.len());
+        assert_eq!(Some(0), value.last_scanned);
+
+        let msg = QueryMsg::ListGames {
+            start_after: value.last_scanned,
+            limit: Some(1),
+            status_filter: Some(GameFilter::Active),
+        };
+        let value: ListGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(1, value.games.len());
+        assert_eq!(1, value.games[0].index);
+
+        let msg = QueryMsg::ListGames {
+            start_after: None,
+            limit: None,
+            status_filter: Some(GameFilter::Finished),
+        };
+        let value: ListGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(0, value.games.len());
+        assert_eq!(Some(1), value.last_scanned);
+    }
+
+    #[test]
+    fn claim_opponent_inactivity() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(1000, "token"));
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let mut env = mock_env("player1", &coins(1000, "token"));
+        env.block.height += PLAYER_DEADLINE_BLOCKS - 1;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let mut env = mock_env("player2", &coins(1000, "token"));
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let mut env = mock_env("someone", &coins(1000, "token"));
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let mut env = mock_env("player1", &coins(1000, "token"));
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address.as_str(), "player1");
+                assert_eq!(amount.len(), 1);
+                assert_eq!(amount[0].denom, "uscrt");
+                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 2));
+            }
+            _ => {
+                panic!("Expected claim for inactivity");
+            }
+        }
+
+        // Can't double claim
+        let mut env = mock_env("player1", &coins(1000, "token"));
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(2) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        let outcome = value.outcome.unwrap();
+        assert_eq!(Some(HumanAddr::from("player1")), outcome.winner);
+        assert_eq!(EndReason::Inactivity, outcome.reason);
+        assert_eq!(Uint128(FUNDING_AMOUNT * 2), outcome.payout);
+        assert_eq!(
+            mock_env("player1", &[]).block.height + PLAYER_DEADLINE_BLOCKS,
+            outcome.end_height
+        );
+    }
+
+    #[test]
+    fn opponent_move_keeps_laggard_clock_running() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let start = mock_env("player1", &[]).block.height;
+
+        let mut env = mock_env("player1", &[]);
+        env.block.height += 8;
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(start, value.round_start);
+        assert_eq!(
+            PLAYER_DEADLINE_BLOCKS - 8 + PLAYER_INCREMENT_BLOCKS,
+            value.player1_time_bank
+        );
+        assert_eq!(PLAYER_DEADLINE_BLOCKS, value.player2_time_bank);
+        assert_eq!(None, value.player1_deadline);
+        assert_eq!(Some(start + PLAYER_DEADLINE_BLOCKS), value.player2_deadline);
+        assert_eq!(start + PLAYER_DEADLINE_BLOCKS, value.deadline);
+
+        // player1's move doesn't extend player2's deadline
+        let mut env = mock_env("player1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn session_key_plays_for_player() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        for (player, n) in &[("player1", 1), ("player2", 2)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(*n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        // Only players can authorize a key
+        let env = mock_env("someone", &[]);
+        let msg = HandleMsg::AuthorizeSessionKey {
+            locator: loc(1),
+            session_addr: HumanAddr::from("someone"),
+            expires_at: env.block.height + 100,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::AuthorizeSessionKey {
+            locator: loc(1),
+            session_addr: HumanAddr::from("session1"),
+            expires_at: env.block.height + 100,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("session1", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.player1_played);
+
+        let mut env = mock_env("session1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send { to_address, .. }) => {
+                assert_eq!(to_address.as_str(), "player1");
+            }
+            _ => panic!("Expected payout to player"),
+        }
+    }
+
+    #[test]
+    fn session_key_expires() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        for (player, n) in &[("player1", 1), ("player2", 2)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(*n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::AuthorizeSessionKey {
+            locator: loc(2),
+            session_addr: HumanAddr::from("session2"),
+            expires_at: env.block.height + 1,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let mut env = mock_env("session2", &[]);
+        env.block.height += 1;
+        let msg = HandleMsg::PlayHand {
+            locator: loc(2),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("You are not a player")
+        );
+    }
+
+    #[test]
+    fn claim_inactivity_time_deadline() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: Some(DeadlinePolicy {
+                unit: DeadlineUnit::Seconds,
+                duration: 1,
+                increment: 0,
+            }),
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: Some(DeadlinePolicy {
+                unit: DeadlineUnit::Seconds,
+                duration: 120,
+                increment: 0,
+            }),
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(2),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(DeadlineUnit::Seconds, value.deadline_unit);
+        assert_eq!(env.block.time + 120, value.deadline);
+
+        // Blocks passing don't matter
+        let mut env = mock_env("player2", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS * 10;
+        env.block.time += 119;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(2),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let mut env = mock_env("player2", &[]);
+        env.block.time += 120;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(2),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    /// Stakes held for the given locators' lobby entries and unfinished games
+    fn escrowed_stakes<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>,
+        locators: &[String],
+    ) -> u128 {
+        let mut games = vec![];
+        let mut total = 0;
+        for locator in locators {
+            let mut bytes = [0u8; 32];
+            hex::decode_to_slice(locator, &mut bytes as &mut [u8]).unwrap();
+            let locator = Locator::load(&deps.storage, bytes).unwrap();
+            if games.contains(&locator.game) {
+                continue;
+            }
+            games.push(locator.game);
+            match Game::may_load(&deps.storage, locator.game).unwrap() {
+                None if locator.settlement == Settlement::Open => total += locator.stake.u128(),
+                None => {}
+                Some(game) => match game.series {
+                    Some(id) => {
+                        let series = Series::load(&deps.storage, id).unwrap();
+                        if series.outcome.is_none() {
+                            total += series.stake.u128() * 2;
+                        }
+                    }
+                    None if !game.game_over => total += game.stake.u128() * 2,
+                    None => {}
+                },
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn stats_track_escrow() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        for (player, n) in &[("player1", 1), ("player2", 2), ("player3", 3)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(*n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let private_games = [
+            ("player4", 4, 1, Some(Uint128(FUNDING_AMOUNT / 2)), None),
+            ("player5", 5, 1, None, None),
+            ("player6", 6, 2, None, Some(3)),
+            ("player7", 7, 2, None, None),
+            ("player8", 8, 3, None, None),
+        ];
+        for (player, n, code, stake, series) in &private_games {
+            let funds = stake.map_or(FUNDING_AMOUNT, |s| s.u128());
+            let env = mock_env(*player, &coins(funds, "uscrt"));
+            let msg = HandleMsg::PrivateGame {
+                locator: loc(*n),
+                invite: invite(*code),
+                stake: *stake,
+                deadline: None,
+                series: *series,
+                discoverable: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let env = mock_env("player8", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(8),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let mut env = mock_env("player1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OfferRematch {
+            locator: loc(2),
+            new_locator: loc(9),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::ContractStats {}).unwrap();
+        let stats: ContractStats = from_binary(&res).unwrap();
+        assert_eq!(3, stats.games_created);
+        assert_eq!(1, stats.games_finished);
+        assert_eq!(1, stats.public_games);
+        assert_eq!(2, stats.private_games);
+        assert_eq!(1, stats.inactivity_claims);
+        assert_eq!(1, stats.lobby_cancellations);
+        assert_eq!(Uint128(FUNDING_AMOUNT * 8), stats.total_staked);
+        assert_eq!(Uint128(FUNDING_AMOUNT * 3), stats.total_paid_out);
+
+        let locators: Vec<String> = (1..10).map(loc).collect();
+        assert_eq!(escrowed_stakes(&deps, &locators), stats.escrowed.u128());
+        assert_eq!(FUNDING_AMOUNT * 5, stats.escrowed.u128());
+    }
+
+    /// Xorshift generator so that failing sequences can be replayed from their seed
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+    }
+
+    /// Random joins, plays, claims and cancels across players and heights keep
+    /// deposits equal to payouts plus escrow and settle every game at most once
+    #[test]
+    fn random_play_conserves_funds() {
+        let players: Vec<HumanAddr> = (0..6)
+            .map(|n| HumanAddr::from(format!("player{}", n)))
+            .collect();
+        let handsigns = [Handsign::ROCK, Handsign::PAPR, Handsign::SCRS];
+        let stakes = [0, FUNDING_AMOUNT / 2, FUNDING_AMOUNT];
+        for seed in 1..20u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+            init(&mut deps, mock_env("creator", &[]), InitMsg {}).unwrap();
+
+            let mut height = mock_env("creator", &[]).block.height;
+            let mut locators: Vec<(String, u128)> = vec![];
+            let mut invites: Vec<(String, u128)> = vec![];
+            let mut settled_games = vec![];
+            let mut refunded_locators = vec![];
+            let mut deposited = 0u128;
+            let mut paid = 0u128;
+            for step in 0..300 {
+                let player = players[rng.next(players.len() as u64) as usize].clone();
+                let known = match locators.len() {
+                    0 => None,
+                    n => Some(locators[rng.next(n as u64) as usize].clone()),
+                };
+                let (msg, funds) = match (rng.next(6), known) {
+                    (0, _) => {
+                        let free_play = rng.next(4) == 0;
+                        let stake = if free_play { 0 } else { FUNDING_AMOUNT };
+                        let locator = format!("{:0>64}", format!("{:x}a{:x}", seed, step));
+                        locators.push((locator.clone(), stake));
+                        (
+                            HandleMsg::JoinGame {
+                                locator,
+                                free_play,
+                                entropy: None,
+                                padding: None,
+                            },
+                            stake,
+                        )
+                    }
+                    (1, _) => {
+                        let stake = stakes[rng.next(stakes.len() as u64) as usize];
+                        let series = if rng.next(4) == 0 { Some(3) } else { None };
+                        let locator = format!("{:0>64}", format!("{:x}f{:x}", seed, step));
+                        let invite = format!("{:0>64}", format!("{:x}e{:x}", seed, step));
+                        locators.push((locator.clone(), stake));
+                        invites.push((invite.clone(), stake));
+                        let msg = HandleMsg::PrivateGame {
+                            locator,
+                            invite,
+                            stake: Some(Uint128(stake)),
+                            deadline: None,
+                            series,
+                            discoverable: false,
+                            entropy: None,
+                            padding: None,
+                        };
+                        (msg, stake)
+                    }
+                    (2, Some(_)) if !invites.is_empty() => {
+                        let (invite, stake) =
+                            invites[rng.next(invites.len() as u64) as usize].clone();
+                        let locator = format!("{:0>64}", format!("{:x}b{:x}", seed, step));
+                        locators.push((locator.clone(), stake));
+                        let msg = HandleMsg::PrivateGame {
+                            locator,
+                            invite,
+                            stake: None,
+                            deadline: None,
+                            series: None,
+                            discoverable: false,
+                            entropy: None,
+                            padding: None,
+                        };
+                        (msg, stake)
+                    }
+                    (3, Some((locator, _))) => {
+                        let handsign = handsigns[rng.next(3) as usize];
+                        (
+                            HandleMsg::PlayHand {
+                                locator,
+                                handsign,
+                                entropy: None,
+                                padding: None,
+                            },
+                            0,
+                        )
+                    }
+                    (4, Some((locator, _))) => (
+                        HandleMsg::ClaimInactivity {
+                            locator,
+                            entropy: None,
+                            padding: None,
+                        },
+                        0,
+                    ),
+                    _ => {
+                        height += rng.next(30);
+                        continue;
+                    }
+                };
+
+                // game a locator points to before the call, to tell lobby refunds from payouts
+                let target = match &msg {
+                    HandleMsg::PlayHand { locator, .. }
+                    | HandleMsg::ClaimInactivity { locator, .. } => {
+                        let mut bytes = [0u8; 32];
+                        hex::decode_to_slice(locator, &mut bytes as &mut [u8]).unwrap();
+                        Locator::may_load(&deps.storage, bytes).unwrap().map(|l| {
+                            (
+                                bytes,
+                                l.game,
+                                Game::may_load(&deps.storage, l.game).unwrap(),
+                            )
+                        })
+                    }
+                    _ => None,
+                };
+                let mut env = mock_env(player, &coins(funds, "uscrt"));
+                env.block.height = height;
+                env.block.time += height;
+                let res = match handle(&mut deps, env, msg) {
+                    Ok(res) => res,
+                    Err(_) => continue,
+                };
+                deposited += funds;
+                let sent: u128 = res
+                    .messages
+                    .iter()
+                    .map(|m| match m {
+                        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount[0].amount.u128(),
+                        _ => panic!("unexpected message"),
+                    })
+                    .sum();
+                if sent > 0 {
+                    match target {
+                        Some((locator, _, None)) => {
+                            assert!(!refunded_locators.contains(&locator), "seed {}", seed);
+                            refunded_locators.push(locator);
+                        }
+                        Some((_, game, Some(_))) => {
+                            assert!(!settled_games.contains(&game), "seed {}", seed);
+                            settled_games.push(game);
+                        }
+                        None => panic!("seed {} paid out on a join", seed),
+                    }
+                }
+                paid += sent;
+                assert!(paid <= deposited, "seed {} paid more than deposited", seed);
+
+                let res = query(&deps, QueryMsg::ContractStats {}).unwrap();
+                let stats: ContractStats = from_binary(&res).unwrap();
+                assert_eq!(deposited - paid, stats.escrowed.u128(), "seed {}", seed);
+                let all: Vec<String> = locators.iter().map(|(l, _)| l.clone()).collect();
+                let held: Vec<String> = all
+                    .into_iter()
+                    .filter(|l| {
+                        let mut bytes = [0u8; 32];
+                        hex::decode_to_slice(l, &mut bytes as &mut [u8]).unwrap();
+                        Locator::may_load(&deps.storage, bytes).unwrap().is_some()
+                    })
+                    .collect();
+                assert_eq!(
+                    deposited - paid,
+                    escrowed_stakes(&deps, &held),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn house_game() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        // House needs a bankroll
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PlayHouse {
+            locator: loc(1),
+            stake: None,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        handle(
+            &mut deps,
+            env,
+            HandleMsg::FundHouse {
+                entropy: None,
+                padding: None,
+            },
+        )
+        .unwrap_err();
+        let env = mock_env("creator", &coins(FUNDING_AMOUNT * 20, "uscrt"));
+        handle(
+            &mut deps,
+            env,
+            HandleMsg::FundHouse {
+                entropy: None,
+                padding: None,
+            },
+        )
+        .unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PlayHouse {
+            locator: loc(1),
+            stake: None,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.player2_played);
+        // house move is only drawn when the player plays
+        let bytes = decode_hex32(&loc(1), "").unwrap();
+        let id = Locator::load(&deps.storage, bytes).unwrap().game;
+        assert_eq!(
+            None,
+            Game::load(&deps.storage, id).unwrap().player2_handsign
+        );
+        let env = mock_env("creator", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        assert_eq!(
+            handle(&mut deps, env, msg).unwrap_err(),
+            StdError::generic_err("already_played")
+        );
+        // admin claims for the house once the player runs out of time
+        let height = mock_env("creator", &[]).block.height;
+        let value = claimability_of(&deps, loc(1), "creator", Some(height));
+        assert_eq!(false, value.can_claim);
+        assert_eq!(Some(height + PLAYER_DEADLINE_BLOCKS), value.claimable_from);
+
+        // Exposure is capped per block
+        let env = mock_env("player2", &coins(MAX_HOUSE_EXPOSURE_PER_BLOCK, "uscrt"));
+        let msg = HandleMsg::PlayHouse {
+            locator: loc(2),
+            stake: Some(Uint128(MAX_HOUSE_EXPOSURE_PER_BLOCK)),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("forbidden house exposure limit")
+        );
+
+        let mut payout = None;
+        for round in 0..100 {
+            let mut env = mock_env("player1", &[]);
+            env.block.height += 2 * round;
+            let msg = HandleMsg::PlayHand {
+                locator: loc(1),
+                handsign: Handsign::ROCK,
+                entropy: None,
+                padding: None,
+            };
+            let res = handle(&mut deps, env.clone(), msg).unwrap();
+            let value: HandleAnswer = from_binary(&res.data.unwrap()).unwrap();
+            match value {
+                HandleAnswer::PlayHand { round_resolved, .. } => assert_eq!(false, round_resolved),
+                _ => panic!("Expected PlayHand"),
+            }
+            // house move is drawn but the round waits for a later block
+            let msg = HandleMsg::ResolveHouse {
+                locator: loc(1),
+                entropy: None,
+                padding: None,
+            };
+            assert_eq!(
+                handle(&mut deps, env.clone(), msg.clone()).unwrap_err(),
+                StdError::generic_err("forbidden house move resolves in a later block")
+            );
+            env.block.height += 1;
+            let res = handle(&mut deps, env, msg).unwrap();
+            let value: GameStatusResponse =
+                from_binary(&query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap())
+                    .unwrap();
+            if value.game_over {
+                payout = Some(res.messages.len());
+                break;
+            }
+            assert_eq!(true, value.player2_played);
+            assert_eq!(
+                None,
+                Game::load(&deps.storage, id).unwrap().player2_handsign
+            );
+        }
+
+        let res = query(&deps, QueryMsg::House {}).unwrap();
+        let house: HouseResponse = from_binary(&res).unwrap();
+        let res = query(&deps, QueryMsg::ContractStats {}).unwrap();
+        let stats: ContractStats = from_binary(&res).unwrap();
+        assert_eq!(Uint128::zero(), stats.escrowed);
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let outcome = from_binary::<GameStatusResponse>(&res)
+            .unwrap()
+            .outcome
+            .unwrap();
+        if outcome.winner == Some(HumanAddr::from("player1")) {
+            assert_eq!(Some(1), payout);
+            assert_eq!(Uint128(FUNDING_AMOUNT * 19), house.bankroll);
+            assert_eq!(Uint128(FUNDING_AMOUNT * 2), stats.total_paid_out);
+        } else {
+            assert_eq!(Some(0), payout);
+            assert_eq!(Uint128(FUNDING_AMOUNT * 21), house.bankroll);
+            assert_eq!(Uint128::zero(), stats.total_paid_out);
+            assert_eq!(Uint128(FUNDING_AMOUNT * 2), stats.returned_to_house);
+        }
+    }
+
+    #[test]
+    fn room_game() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OpenRoom {
+            locator: loc(1),
+            room: invite(1),
+            size: 3,
+            stake: None,
+            deadline: None,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinRoom {
+            locator: loc(2),
+            room: invite(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Player leaves before the room fills up and gets their stake back
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(2),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        for n in 3..5 {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinRoom {
+                locator: loc(n),
+                room: invite(1),
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        let env = mock_env("player5", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinRoom {
+            locator: loc(5),
+            room: invite(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("forbidden room already started")
+        );
+
+        // Scissors is beaten by rock, then the paper beats the rock
+        for (n, handsign) in &[
+            (1, Handsign::ROCK),
+            (3, Handsign::SCRS),
+            (4, Handsign::ROCK),
+        ] {
+            let env = mock_env(format!("player{}", n), &[]);
+            let msg = HandleMsg::PlayHand {
+                locator: loc(*n),
+                handsign: *handsign,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let res = query(&deps, QueryMsg::RoomStatus { locator: loc(4) }).unwrap();
+        let value: RoomStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.started);
+        assert_eq!(2, value.round);
+        let eliminated: Vec<bool> = value.players.iter().map(|p| p.eliminated).collect();
+        assert_eq!(vec![false, true, false], eliminated);
+
+        // Two player queries don't answer for rooms
+        let res = query(&deps, QueryMsg::GameLobby { locator: loc(1) });
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("bad_request game_lobby is for two player games")
+        );
+        let msg = QueryMsg::MyGameStatus {
+            locator: loc(1),
+            height: None,
+            time: None,
+        };
+        assert_eq!(
+            query(&deps, msg).unwrap_err(),
+            StdError::generic_err("bad_request my_game_status is for two player games")
+        );
+
+        // Session key plays for the player who authorized it
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::AuthorizeSessionKey {
+            locator: loc(1),
+            session_addr: HumanAddr::from("session1"),
+            expires_at: env.block.height + 100,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("session1", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::PAPR,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("player4", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(4),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address.as_str(), "player1");
+                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 3));
+            }
+            _ => panic!("Expected payout to the last player standing"),
+        }
+        let res = query(&deps, QueryMsg::RoomStatus { locator: loc(1) }).unwrap();
+        let value: RoomStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.game_over);
+
+        let stats = load_stats(&deps.storage).unwrap();
+        assert_eq!(Uint128(0), stats.escrowed);
+        assert_eq!(1, stats.room_games);
+    }
+
+    #[test]
+    fn abandoned_room_refunds_stakes() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OpenRoom {
+            locator: loc(1),
+            room: invite(1),
+            size: 3,
+            stake: None,
+            deadline: None,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        for n in 2..4 {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinRoom {
+                locator: loc(n),
+                room: invite(1),
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        // Started rooms are listed with the two player games
+        let msg = QueryMsg::ListGames {
+            start_after: None,
+            limit: None,
+            status_filter: Some(GameFilter::Active),
+        };
+        let value: ListGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(1, value.games.len());
+        assert_eq!(3, value.games[0].players);
+        let deadline = mock_env("player1", &[]).block.height + PLAYER_DEADLINE_BLOCKS;
+        assert_eq!(deadline, value.games[0].deadline);
+
+        // Nobody played, anyone can end the room once every deadline passed
+        let mut env = mock_env("stranger", &[]);
+        env.block.height = deadline - 1;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        assert_eq!(
+            handle(&mut deps, env.clone(), msg.clone()).unwrap_err(),
+            StdError::generic_err("You are not a player")
+        );
+        env.block.height = deadline;
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(3, res.messages.len());
+        for (n, message) in res.messages.iter().enumerate() {
+            match message {
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address, amount, ..
+                }) => {
+                    assert_eq!(to_address.to_string(), format!("player{}", n + 1));
+                    assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT));
+                }
+                _ => panic!("Expected refunds to the standing players"),
+            }
+        }
+        let res = query(&deps, QueryMsg::RoomStatus { locator: loc(2) }).unwrap();
+        let value: RoomStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(EndReason::Aborted, value.outcome.unwrap().reason);
+        let stats = load_stats(&deps.storage).unwrap();
+        assert_eq!(Uint128(0), stats.escrowed);
+        let msg = QueryMsg::ListGames {
+            start_after: None,
+            limit: None,
+            status_filter: Some(GameFilter::Finished),
+        };
+        let value: ListGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(1, value.games.len());
+    }
+
+    #[test]
+    fn team_game() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OpenTeamGame {
+            locator: loc(1),
+            room: invite(1),
+            team: Team::A,
+            stake: None,
+            deadline: None,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        for (n, team) in &[(2, Team::B), (3, Team::A), (4, Team::B)] {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinTeamGame {
+                locator: loc(*n),
+                room: invite(1),
+                team: *team,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let env = mock_env("player4", &[]);
+        let msg = HandleMsg::AuthorizeSessionKey {
+            locator: loc(4),
+            session_addr: HumanAddr::from("session4"),
+            expires_at: env.block.height + 100,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Team A wins both duels of every round, player4 plays with their session key
+        let mut res = HandleResponse::default();
+        for _ in 0..3 {
+            for (n, handsign) in &[
+                (1, Handsign::ROCK),
+                (2, Handsign::SCRS),
+                (3, Handsign::PAPR),
+                (4, Handsign::ROCK),
+            ] {
+                let sender = match n {
+                    4 => "session4".to_string(),
+                    _ => format!("player{}", n),
+                };
+                let env = mock_env(sender, &[]);
+                let msg = HandleMsg::PlayHand {
+                    locator: loc(*n),
+                    handsign: *handsign,
+                    entropy: None,
+                    padding: None,
+                };
+                res = handle(&mut deps, env, msg).unwrap();
+            }
+        }
+        let res_status = query(&deps, QueryMsg::TeamGameStatus { locator: loc(2) }).unwrap();
+        let value: TeamGameStatusResponse = from_binary(&res_status).unwrap();
+        assert_eq!(true, value.game_over);
+        assert_eq!((6, 0), (value.team_a_wins, value.team_b_wins));
+        assert_eq!(Some(Team::A), value.outcome.unwrap().winner);
+
+        let paid: Vec<(String, Uint128)> = res
+            .messages
+            .iter()
+            .map(|m| match m {
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address, amount, ..
+                }) => (to_address.to_string(), amount[0].amount),
+                _ => panic!("Expected payout to the winning team"),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("player1".to_string(), Uint128(FUNDING_AMOUNT * 2)),
+                ("player3".to_string(), Uint128(FUNDING_AMOUNT * 2)),
+            ],
+            paid
+        );
+        let stats = load_stats(&deps.storage).unwrap();
+        assert_eq!(Uint128(0), stats.escrowed);
+        assert_eq!(1, stats.team_games);
+    }
+
+    #[test]
+    fn abandoned_team_game_refunds_stakes() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OpenTeamGame {
+            locator: loc(1),
+            room: invite(1),
+            team: Team::A,
+            stake: None,
+            deadline: None,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        for (n, team) in &[(2, Team::B), (3, Team::A), (4, Team::B)] {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinTeamGame {
+                locator: loc(*n),
+                room: invite(1),
+                team: *team,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        // Nobody played, anyone can abort once both duels ran out of time
+        let deadline = mock_env("player1", &[]).block.height + PLAYER_DEADLINE_BLOCKS;
+        let mut env = mock_env("stranger", &[]);
+        env.block.height = deadline - 1;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        assert_eq!(
+            handle(&mut deps, env.clone(), msg.clone()).unwrap_err(),
+            StdError::generic_err("You are not a player")
+        );
+        env.block.height = deadline;
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(4, res.messages.len());
+        for message in &res.messages {
+            match message {
+                CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+                    assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT))
+                }
+                _ => panic!("Expected refunds to every player"),
+            }
+        }
+        let res = query(&deps, QueryMsg::TeamGameStatus { locator: loc(3) }).unwrap();
+        let value: TeamGameStatusResponse = from_binary(&res).unwrap();
+        let outcome = value.outcome.unwrap();
+        assert_eq!(None, outcome.winner);
+        assert_eq!(EndReason::Aborted, outcome.reason);
+        assert_eq!(1, value.round);
+        let stats = load_stats(&deps.storage).unwrap();
+        assert_eq!(Uint128(0), stats.escrowed);
+    }
+
+    #[test]
+    fn play_hand_input_size() {
+        let sizes: Vec<usize> = [Handsign::ROCK, Handsign::PAPR, Handsign::SCRS]
+            .iter()
+            .map(|handsign| {
+                let msg = HandleMsg::PlayHand {
+                    locator: loc(1),
+                    handsign: *handsign,
+                    entropy: None,
+                    padding: None,
+                };
+                to_vec(&msg).unwrap().len()
+            })
+            .collect();
+        assert_eq!(vec![sizes[0]; 3], sizes);
+
+        // Padding is accepted and ignored
+        let msg: HandleMsg = from_slice(
+            format!(
+                r#"{{"play_hand":{{"locator":"{}","handsign":"ROCK","padding":"    "}}}}"#,
+                loc(1)
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        match msg {
+            HandleMsg::PlayHand { padding, .. } => assert_eq!(Some("    ".to_string()), padding),
+            _ => panic!("Expected PlayHand"),
+        }
+
+        // Locators must be exactly 64 lowercase hex characters
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        init(&mut deps, mock_env("creator", &[]), InitMsg {}).unwrap();
+        for locator in &[loc(1)[..62].to_string(), loc(1).to_uppercase()] {
+            let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: locator.clone(),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            assert_eq!(
+                handle(&mut deps, env, msg).unwrap_err(),
+                StdError::generic_err("bad_request invalid_locator")
+            );
+        }
+    }
+
+    #[test]
+    fn claim_many() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        init(&mut deps, mock_env("creator", &[]), InitMsg {}).unwrap();
+
+        for n in 1..3 {
+            let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::PrivateGame {
+                locator: loc(n),
+                invite: invite(n),
+                stake: None,
+                deadline: None,
+                series: None,
+                discoverable: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimMany {
+            locators: vec![loc(1), loc(2), loc(1), "zz".to_string()],
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+
+        // Both refunds go out in one message
+        assert_eq!(1, res.messages.len());
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address.as_str(), "player1");
+                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 2));
+            }
+            _ => panic!("Expected combined refund"),
+        }
+        let results = match from_binary(&res.data.unwrap()).unwrap() {
+            HandleAnswer::ClaimMany { results } => results,
+            _ => panic!("Expected ClaimMany answer"),
+        };
+        let success: Vec<bool> = results.iter().map(|r| r.success).collect();
+        assert_eq!(vec![true, true, false, false], success);
+        assert_eq!(
+            Some("forbidden game canceled".to_string()),
+            results[2].error
+        );
+        assert_eq!(
+            Some("bad_request invalid_locator".to_string()),
+            results[3].error
+        );
+        let stats = load_stats(&deps.storage).unwrap();
+        assert_eq!(Uint128(0), stats.escrowed);
+    }
+
+    #[test]
+    fn handle_answers() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        init(&mut deps, mock_env("creator", &[]), InitMsg {}).unwrap();
+
+        let mut answers = vec![];
+        for n in 1..3 {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            let res = handle(&mut deps, env, msg).unwrap();
+            answers.push(from_binary::<HandleAnswer>(&res.data.unwrap()).unwrap());
+        }
+        for (n, handsign) in &[(1, Handsign::ROCK), (2, Handsign::SCRS)] {
+            let env = mock_env(format!("player{}", n), &[]);
+            let msg = HandleMsg::PlayHand {
+                locator: loc(*n),
+                handsign: *handsign,
+                entropy: None,
+                padding: None,
+            };
+            let res = handle(&mut deps, env, msg).unwrap();
+            answers.push(from_binary::<HandleAnswer>(&res.data.unwrap()).unwrap());
+        }
+        assert_eq!(
+            vec![
+                HandleAnswer::JoinGame {
+                    game_started: false,
+                    player1: true,
+                },
+                HandleAnswer::JoinGame {
+                    game_started: true,
+                    player1: false,
+                },
+                HandleAnswer::PlayHand {
+                    round_resolved: false,
+                    round: 1,
+                    wins: 0,
+                    opponent_wins: 0,
+                    game_over: false,
+                },
+                HandleAnswer::PlayHand {
+                    round_resolved: true,
+                    round: 1,
+                    wins: 0,
+                    opponent_wins: 1,
+                    game_over: false,
+                },
+            ],
+            answers
+        );
+    }
+
+    #[test]
+    fn rematch() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Game must be over
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OfferRematch {
+            locator: loc(2),
+            new_locator: loc(3),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let mut env = mock_env("player1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT - 1, "uscrt"));
+        let msg = HandleMsg::OfferRematch {
+            locator: loc(2),
+            new_locator: loc(3),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::OfferRematch {
+            locator: loc(2),
+            new_locator: loc(3),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::AcceptRematch {
+            locator: loc(2),
+            new_locator: loc(4),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        let rematch = value.rematch.unwrap();
+        assert_eq!(HumanAddr::from("player2"), rematch.offered_by);
+        assert_eq!(false, rematch.started);
+
+        // Locators can't be reused
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::AcceptRematch {
             locator: loc(1),
-            handsign: Handsign::ROCK,
+            new_locator: loc(2),
+            entropy: None,
+            padding: None,
         };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        handle(&mut deps, env, msg).unwrap_err();
 
-        let env = mock_env("player2", &coins(2, "token"));
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::AcceptRematch {
+            locator: loc(1),
+            new_locator: loc(4),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.rematch.unwrap().started);
+
+        let msg = QueryMsg::GameLobby { locator: loc(4) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.game_started);
+        assert_eq!(true, value.player1_locator);
+
+        let env = mock_env("player2", &[]);
         let msg = HandleMsg::PlayHand {
-            locator: loc(2),
+            locator: loc(3),
             handsign: Handsign::PAPR,
+            entropy: None,
+            padding: None,
         };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        handle(&mut deps, env, msg).unwrap();
 
-        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(4) }).unwrap();
         let value: GameStatusResponse = from_binary(&res).unwrap();
-        assert_eq!(0, value.player1_wins);
-        assert_eq!(1, value.player2_wins);
+        assert_eq!(1, value.round);
+        assert_eq!(true, value.player2_played);
         assert_eq!(false, value.game_over);
+        assert_eq!(None, value.outcome);
+    }
+
+    #[test]
+    fn series_pays_out_at_the_end() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: Some(4),
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: Some(3),
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let res = query(&deps, QueryMsg::Bets { locator: loc(1) }).unwrap();
+        let value: BetsResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.open);
+
+        // player1 wins the first game, player2 the next two
+        let games = [
+            (Handsign::ROCK, Handsign::SCRS),
+            (Handsign::ROCK, Handsign::PAPR),
+            (Handsign::SCRS, Handsign::ROCK),
+        ];
+        let mut payouts = 0;
+        for (g, (hand1, hand2)) in games.iter().enumerate() {
+            for _ in 0..WINS_TO_FINISH {
+                let env = mock_env("player1", &[]);
+                let msg = HandleMsg::PlayHand {
+                    locator: loc(1),
+                    handsign: *hand1,
+                    entropy: None,
+                    padding: None,
+                };
+                handle(&mut deps, env, msg).unwrap();
+
+                let env = mock_env("player2", &[]);
+                let msg = HandleMsg::PlayHand {
+                    locator: loc(2),
+                    handsign: *hand2,
+                    entropy: None,
+                    padding: None,
+                };
+                let res = handle(&mut deps, env, msg).unwrap();
+                if g < games.len() - 1 {
+                    assert_eq!(res.messages.len(), 0);
+                }
+                if g == games.len() - 1 && !res.messages.is_empty() {
+                    payouts += 1;
+                    match &res.messages[0] {
+                        CosmosMsg::Bank(BankMsg::Send {
+                            to_address, amount, ..
+                        }) => {
+                            assert_eq!(to_address.as_str(), "player2");
+                            assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 2));
+                        }
+                        _ => {
+                            panic!("Expected payout for series winner");
+                        }
+                    }
+                }
+            }
+
+            let res = query(&deps, QueryMsg::SeriesStatus { locator: loc(1) }).unwrap();
+            let value: SeriesStatusResponse = from_binary(&res).unwrap();
+            assert_eq!(g + 1, value.results.len());
+            if g == 0 {
+                assert_eq!(1, value.player1_wins);
+                assert_eq!(false, value.current_game.game_over);
+                assert_eq!(1, value.current_game.round);
+
+                // betting on the series closed with its first game
+                let res = query(&deps, QueryMsg::Bets { locator: loc(1) }).unwrap();
+                let value: BetsResponse = from_binary(&res).unwrap();
+                assert_eq!(false, value.open);
+                let env = mock_env("spectator1", &coins(100, "uscrt"));
+                let msg = HandleMsg::PlaceBet {
+                    locator: loc(1),
+                    side: BetSide::Player1,
+                    entropy: None,
+                    padding: None,
+                };
+                assert_eq!(
+                    handle(&mut deps, env, msg).unwrap_err(),
+                    StdError::generic_err("forbidden betting closed")
+                );
+            }
+        }
+
+        assert_eq!(1, payouts);
+
+        let res = query(&deps, QueryMsg::SeriesStatus { locator: loc(1) }).unwrap();
+        let value: SeriesStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.player1_wins);
+        assert_eq!(2, value.player2_wins);
+        assert_eq!(true, value.current_game.game_over);
+        let outcome = value.outcome.unwrap();
+        assert_eq!(Some(HumanAddr::from("player2")), outcome.winner);
+        assert_eq!(Uint128(FUNDING_AMOUNT * 2), outcome.payout);
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn claim_lobby_inactivity() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Only the waiting player gets the refund
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("You are not a player")
+        );
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address.as_str(), "player1");
+                assert_eq!(amount.len(), 1);
+                assert_eq!(amount[0].denom, "uscrt");
+                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT));
+            }
+            _ => {
+                panic!("Expected claim for inactivity");
+            }
+        }
+
+        // Can't double-claim
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        // Lobby becomes empty
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::GameLobby { locator: loc(2) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.game_started);
+    }
+
+    #[test]
+    fn matched_stake_settles_once() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        init(&mut deps, env, InitMsg {}).unwrap();
+
+        for (player, n) in &[("player1", 1), ("player2", 2)] {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::PrivateGame {
+                locator: loc(*n),
+                invite: invite(1),
+                stake: None,
+                deadline: None,
+                series: None,
+                discoverable: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+        }
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(loc(1), &mut bytes as &mut [u8]).unwrap();
+        let locator = Locator::load(&deps.storage, bytes).unwrap();
+        assert_eq!(Settlement::Matched, locator.settlement);
+
+        // Matched player can't take a lobby refund
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("unable to claim inactivity")
+        );
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::PlayHand {
+            locator: loc(1),
+            handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+        let mut env = mock_env("player1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let locator = Locator::load(&deps.storage, bytes).unwrap();
+        assert_eq!(Settlement::Paid, locator.settlement);
+    }
+
+    #[test]
+    fn claim_private_lobby_inactivity() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let res = handle(&mut deps, env, msg).unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address.as_str(), "player1");
+                assert_eq!(amount.len(), 1);
+                assert_eq!(amount[0].denom, "uscrt");
+                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT));
+            }
+            _ => {
+                panic!("Expected claim for inactivity");
+            }
+        }
+
+        // Can't double-claim
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        // Lobby becomes non-joinable
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn free_play_game() {
+        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
+        let env = mock_env("creator", &[]);
+        let msg = InitMsg {};
+        init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("player1", &[]);
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: true,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Staked players don't match with free players
+        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(3),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // Free players can't stake
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: true,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player2", &[]);
+        let msg = HandleMsg::JoinGame {
+            locator: loc(2),
+            free_play: true,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::GameLobby { locator: loc(3) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.game_started);
+
+        for _ in 0..WINS_TO_FINISH {
+            let env = mock_env("player1", &[]);
+            let msg = HandleMsg::PlayHand {
+                locator: loc(1),
+                handsign: Handsign::ROCK,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
+
+            let env = mock_env("player2", &[]);
+            let msg = HandleMsg::PlayHand {
+                locator: loc(2),
+                handsign: Handsign::SCRS,
+                entropy: None,
+                padding: None,
+            };
+            let res = handle(&mut deps, env, msg).unwrap();
+            assert_eq!(res.messages.len(), 0);
+        }
+
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(2) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(WINS_TO_FINISH, value.player1_wins);
+        assert_eq!(true, value.game_over);
+        assert_eq!(Uint128(0), value.stake);
     }
 
     #[test]
-    fn player_win_payout() {
+    fn private_game_stake() {
         let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
         let env = mock_env("creator", &[]);
         let msg = InitMsg {};
-        let _res = init(&mut deps, env, msg).unwrap();
+        init(&mut deps, env, msg).unwrap();
 
-        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        let stake = FUNDING_AMOUNT * 5;
+        let env = mock_env("player1", &coins(stake - 1, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: Some(Uint128(stake)),
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player1", &coins(stake, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: Some(Uint128(stake)),
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let msg = QueryMsg::GameLobby { locator: loc(1) };
+        let res = query(&deps, msg).unwrap();
+        let value: GameLobbyResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.game_started);
+        assert_eq!(Uint128(stake), value.stake);
 
+        // Joiner must match the stake
         let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(2) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player2", &coins(stake, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: Some(Uint128(FUNDING_AMOUNT)),
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
+
+        let env = mock_env("player2", &coins(stake, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
         for r in 0..WINS_TO_FINISH {
-            let env = mock_env("player1", &coins(1000, "token"));
+            let env = mock_env("player1", &[]);
             let msg = HandleMsg::PlayHand {
                 locator: loc(1),
-                handsign: Handsign::ROCK,
+                handsign: Handsign::PAPR,
+                entropy: None,
+                padding: None,
             };
-            let _res = handle(&mut deps, env, msg).unwrap();
+            let res = handle(&mut deps, env, msg).unwrap();
+            if r == WINS_TO_FINISH - 1 {
+                assert_eq!(res.messages.len(), 0);
+            }
 
-            let env = mock_env("player2", &coins(2, "token"));
+            let env = mock_env("player2", &[]);
             let msg = HandleMsg::PlayHand {
                 locator: loc(2),
-                handsign: Handsign::PAPR,
+                handsign: Handsign::SCRS,
+                entropy: None,
+                padding: None,
             };
             let res = handle(&mut deps, env, msg).unwrap();
             if r == WINS_TO_FINISH - 1 {
@@ -401,257 +5092,328 @@ mod tests {
                         to_address, amount, ..
                     }) => {
                         assert_eq!(to_address.as_str(), "player2");
-                        assert_eq!(amount.len(), 1);
-                        assert_eq!(amount[0].denom, "uscrt");
-                        assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 2));
+                        assert_eq!(amount[0].amount, Uint128(stake * 2));
                     }
                     _ => {
                         panic!("Expected payout for winner");
                     }
                 }
-            } else {
-                assert_eq!(res.messages.len(), 0);
             }
         }
-
-        let res = query(&deps, QueryMsg::GameStatus { locator: loc(1) }).unwrap();
-        let value: GameStatusResponse = from_binary(&res).unwrap();
-        assert_eq!(0, value.player1_wins);
-        assert_eq!(WINS_TO_FINISH, value.player2_wins);
-        assert_eq!(true, value.game_over);
     }
 
     #[test]
-    fn game_takes_two_players() {
+    fn spectator_bets() {
         let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
         let env = mock_env("creator", &[]);
         let msg = InitMsg {};
-        let _res = init(&mut deps, env, msg).unwrap();
+        init(&mut deps, env, msg).unwrap();
 
         let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
-        let _res = handle(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(2) };
-        let _res = handle(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(3) };
-        let _res = handle(&mut deps, env, msg);
-
-        let msg = QueryMsg::GameLobby { locator: loc(1) };
-        let res = query(&deps, msg).unwrap();
-        let value: GameLobbyResponse = from_binary(&res).unwrap();
-        assert_eq!(true, value.game_started);
-        assert_eq!(true, value.player1_locator);
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(1),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
-        let msg = QueryMsg::GameLobby { locator: loc(2) };
-        let res = query(&deps, msg).unwrap();
-        let value: GameLobbyResponse = from_binary(&res).unwrap();
-        assert_eq!(true, value.game_started);
-        assert_eq!(false, value.player1_locator);
+        // Game hasn't started
+        let env = mock_env("spectator1", &coins(300, "uscrt"));
+        let msg = HandleMsg::PlaceBet {
+            locator: loc(1),
+            side: BetSide::Player1,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
 
-        let msg = QueryMsg::GameLobby { locator: loc(3) };
-        let res = query(&deps, msg).unwrap();
-        let value: GameLobbyResponse = from_binary(&res).unwrap();
-        assert_eq!(false, value.game_started);
-    }
+        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
+        let msg = HandleMsg::PrivateGame {
+            locator: loc(2),
+            invite: invite(5),
+            stake: None,
+            deadline: None,
+            series: None,
+            discoverable: false,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
-    #[test]
-    fn private_game_matching() {
-        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
-        let env = mock_env("creator", &[]);
-        let msg = InitMsg {};
-        let _res = init(&mut deps, env, msg).unwrap();
+        let env = mock_env("player2", &coins(300, "uscrt"));
+        let msg = HandleMsg::PlaceBet {
+            locator: loc(2),
+            side: BetSide::Player2,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
 
-        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::PrivateGame { locator: loc(5) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("spectator1", &coins(300, "uscrt"));
+        let msg = HandleMsg::PlaceBet {
+            locator: loc(1),
+            side: BetSide::Player1,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
-        // JoinGame shouldn't interfere
-        let env = mock_env("player3", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("spectator2", &coins(100, "uscrt"));
+        let msg = HandleMsg::PlaceBet {
+            locator: loc(1),
+            side: BetSide::Player2,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
-        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::PrivateGame { locator: loc(5) };
-        let _res = handle(&mut deps, env, msg).unwrap();
+        let res = query(&deps, QueryMsg::Bets { locator: loc(1) }).unwrap();
+        let value: BetsResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(300), value.player1_total);
+        assert_eq!(Uint128(100), value.player2_total);
+        assert_eq!(true, value.open);
 
-        let env = mock_env("player1", &coins(1000, "token"));
+        let env = mock_env("player1", &[]);
         let msg = HandleMsg::PlayHand {
-            locator: loc(5),
+            locator: loc(1),
             handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
         };
-        let _res = handle(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player2", &coins(2, "token"));
+        handle(&mut deps, env, msg).unwrap();
+        let env = mock_env("player2", &[]);
         let msg = HandleMsg::PlayHand {
-            locator: loc(5),
-            handsign: Handsign::PAPR,
+            locator: loc(2),
+            handsign: Handsign::SCRS,
+            entropy: None,
+            padding: None,
         };
-        let _res = handle(&mut deps, env, msg).unwrap();
-
-        let res = query(&deps, QueryMsg::GameStatus { locator: loc(5) }).unwrap();
-        let value: GameStatusResponse = from_binary(&res).unwrap();
-        assert_eq!(0, value.player1_wins);
-        assert_eq!(1, value.player2_wins);
-        assert_eq!(false, value.game_over);
-    }
-
-    #[test]
-    fn claim_opponent_inactivity() {
-        let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
-        let env = mock_env("creator", &[]);
-        let msg = InitMsg {};
-        init(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
         handle(&mut deps, env, msg).unwrap();
 
-        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(2) };
-        handle(&mut deps, env, msg).unwrap();
+        // Betting closes when round 1 resolves
+        let env = mock_env("spectator2", &coins(100, "uscrt"));
+        let msg = HandleMsg::PlaceBet {
+            locator: loc(1),
+            side: BetSide::Player2,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap_err();
 
-        let env = mock_env("player1", &coins(1000, "token"));
+        let env = mock_env("player1", &[]);
         let msg = HandleMsg::PlayHand {
             locator: loc(1),
             handsign: Handsign::ROCK,
+            entropy: None,
+            padding: None,
         };
         handle(&mut deps, env, msg).unwrap();
 
-        let mut env = mock_env("player1", &coins(1000, "token"));
-        env.block.height += PLAYER_DEADLINE_BLOCKS - 1;
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap_err();
-
-        let mut env = mock_env("player2", &coins(1000, "token"));
-        env.block.height += PLAYER_DEADLINE_BLOCKS;
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap_err();
-
-        let mut env = mock_env("someone", &coins(1000, "token"));
-        env.block.height += PLAYER_DEADLINE_BLOCKS;
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap_err();
-
-        let mut env = mock_env("player1", &coins(1000, "token"));
-        env.block.height += PLAYER_DEADLINE_BLOCKS;
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
+        let mut env = mock_env("player1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS + PLAYER_INCREMENT_BLOCKS;
+        let msg = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
         let res = handle(&mut deps, env, msg).unwrap();
 
-        assert_eq!(res.messages.len(), 1);
-        match &res.messages[0] {
+        assert_eq!(res.messages.len(), 2);
+        match &res.messages[1] {
             CosmosMsg::Bank(BankMsg::Send {
                 to_address, amount, ..
             }) => {
-                assert_eq!(to_address.as_str(), "player1");
-                assert_eq!(amount.len(), 1);
-                assert_eq!(amount[0].denom, "uscrt");
-                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT * 2));
+                assert_eq!(to_address.as_str(), "spectator1");
+                assert_eq!(amount[0].amount, Uint128(400));
             }
             _ => {
-                panic!("Expected claim for inactivity");
+                panic!("Expected payout for winning bettor");
             }
         }
 
-        // Can't double claim
-        let mut env = mock_env("player1", &coins(1000, "token"));
-        env.block.height += PLAYER_DEADLINE_BLOCKS;
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap_err();
+        let res = query(&deps, QueryMsg::Bets { locator: loc(1) }).unwrap();
+        let value: BetsResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.open);
     }
 
     #[test]
-    fn claim_lobby_inactivity() {
+    fn abandoned_game_refunds_bets() {
         let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
-        let env = mock_env("creator", &[]);
-        let msg = InitMsg {};
-        init(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player1", &[]);
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap_err();
+        init(&mut deps, mock_env("creator", &[]), InitMsg {}).unwrap();
 
-        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player1", &[]);
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        let res = handle(&mut deps, env, msg).unwrap();
-
-        assert_eq!(res.messages.len(), 1);
-        match &res.messages[0] {
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address, amount, ..
-            }) => {
-                assert_eq!(to_address.as_str(), "player1");
-                assert_eq!(amount.len(), 1);
-                assert_eq!(amount[0].denom, "uscrt");
-                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT));
-            }
-            _ => {
-                panic!("Expected claim for inactivity");
-            }
+        for n in 1..3 {
+            let env = mock_env(format!("player{}", n), &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            handle(&mut deps, env, msg).unwrap();
         }
+        let env = mock_env("spectator1", &coins(300, "uscrt"));
+        let msg = HandleMsg::PlaceBet {
+            locator: loc(1),
+            side: BetSide::Player1,
+            entropy: None,
+            padding: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
 
-        // Can't double-claim
-        let env = mock_env("player1", &[]);
-        let msg = HandleMsg::ClaimInactivity { locator: loc(1) };
-        handle(&mut deps, env, msg).unwrap_err();
+        let claim = HandleMsg::ClaimInactivity {
+            locator: loc(1),
+            entropy: None,
+            padding: None,
+        };
+        let mut env = mock_env("spectator1", &[]);
+        env.block.height += PLAYER_DEADLINE_BLOCKS - 1;
+        handle(&mut deps, env.clone(), claim.clone()).unwrap_err();
+        let status = |height: u64| -> MyGameStatusResponse {
+            let msg = QueryMsg::MyGameStatus {
+                locator: loc(2),
+                height: Some(height),
+                time: None,
+            };
+            from_binary(&query(&deps, msg).unwrap()).unwrap()
+        };
+        assert_eq!(GamePhase::WaitingForMe, status(env.block.height).phase);
+        let value = status(env.block.height + 1);
+        assert_eq!(GamePhase::Abandoned, value.phase);
+        assert_eq!(true, value.me.can_claim_inactivity);
+        assert_eq!(true, value.opponent.can_claim_inactivity);
 
-        // Lobby becomes empty
-        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(2) };
-        handle(&mut deps, env, msg).unwrap();
+        // neither player showed up, anyone can abort the game
+        env.block.height += 1;
+        let res = handle(&mut deps, env.clone(), claim.clone()).unwrap();
+        let sent: Vec<(String, u128)> = res
+            .messages
+            .iter()
+            .map(|m| match m {
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address, amount, ..
+                }) => (to_address.to_string(), amount[0].amount.u128()),
+                _ => panic!("Expected refunds"),
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                ("player1".to_string(), FUNDING_AMOUNT),
+                ("player2".to_string(), FUNDING_AMOUNT),
+                ("spectator1".to_string(), 300),
+            ]
+        );
 
-        let msg = QueryMsg::GameLobby { locator: loc(2) };
-        let res = query(&deps, msg).unwrap();
-        let value: GameLobbyResponse = from_binary(&res).unwrap();
-        assert_eq!(false, value.game_started);
+        let res = query(&deps, QueryMsg::GameStatus { locator: loc(2) }).unwrap();
+        let value: GameStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(true, value.game_over);
+        assert_eq!(None, value.outcome.as_ref().unwrap().winner);
+        assert_eq!(EndReason::Aborted, value.outcome.unwrap().reason);
+        let res = query(&deps, QueryMsg::Bets { locator: loc(1) }).unwrap();
+        let value: BetsResponse = from_binary(&res).unwrap();
+        assert_eq!(false, value.open);
+        assert_eq!(
+            handle(&mut deps, env, claim).unwrap_err(),
+            StdError::generic_err("game_over")
+        );
     }
 
     #[test]
-    fn claim_private_lobby_inactivity() {
+    fn permit_queries() {
         let mut deps = mock_dependencies(20, &coins(0, "uscrt"));
         let env = mock_env("creator", &[]);
-        let msg = InitMsg {};
-        init(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player1", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::PrivateGame { locator: loc(5) };
-        handle(&mut deps, env, msg).unwrap();
-
-        let env = mock_env("player1", &[]);
-        let msg = HandleMsg::ClaimInactivity { locator: loc(5) };
-        let res = handle(&mut deps, env, msg).unwrap();
+        let _res = init(&mut deps, env, InitMsg {}).unwrap();
+        let key = [3u8; 32];
+        let player1 = permit::tests::signer(&key);
 
-        assert_eq!(res.messages.len(), 1);
-        match &res.messages[0] {
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address, amount, ..
-            }) => {
-                assert_eq!(to_address.as_str(), "player1");
-                assert_eq!(amount.len(), 1);
-                assert_eq!(amount[0].denom, "uscrt");
-                assert_eq!(amount[0].amount, Uint128(FUNDING_AMOUNT));
-            }
-            _ => {
-                panic!("Expected claim for inactivity");
+        for (n, player) in [(1, player1.as_str()), (2, "player2")].iter() {
+            let env = mock_env(*player, &coins(FUNDING_AMOUNT, "uscrt"));
+            let msg = HandleMsg::JoinGame {
+                locator: loc(*n),
+                free_play: false,
+                entropy: None,
+                padding: None,
+            };
+            let _res = handle(&mut deps, env, msg).unwrap();
+        }
+        for _ in 0..WINS_TO_FINISH {
+            for (n, player, handsign) in [
+                (1, player1.as_str(), Handsign::ROCK),
+                (2, "player2", Handsign::SCRS),
+            ]
+            .iter()
+            {
+                let env = mock_env(*player, &[]);
+                let msg = HandleMsg::PlayHand {
+                    locator: loc(*n),
+                    handsign: *handsign,
+                    entropy: None,
+                    padding: None,
+                };
+                let _res = handle(&mut deps, env, msg).unwrap();
             }
         }
 
-        // Can't double-claim
-        let env = mock_env("player1", &[]);
-        let msg = HandleMsg::ClaimInactivity { locator: loc(5) };
-        handle(&mut deps, env, msg).unwrap_err();
+        let contract = "cosmos2contract";
+        let history =
+            permit::tests::sign_permit(&key, "games", contract, vec![Permission::History]);
+        let msg = QueryMsg::WithPermit {
+            permit: history.clone(),
+            query: PermitQuery::MyGames {
+                start_after: None,
+                limit: None,
+            },
+        };
+        let value: MyGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(value.total, 1);
+        assert_eq!(value.games[0].locator, loc(1));
+        assert_eq!(value.games[0].game_over, true);
+        assert_eq!(value.games[0].won, Some(true));
+        assert_eq!(value.games[0].reason, Some(EndReason::WinsReached));
 
-        // Lobby becomes non-joinable
-        let env = mock_env("player2", &coins(FUNDING_AMOUNT, "uscrt"));
-        let msg = HandleMsg::PrivateGame { locator: loc(5) };
-        handle(&mut deps, env, msg).unwrap_err();
+        let msg = QueryMsg::WithPermit {
+            permit: history.clone(),
+            query: PermitQuery::MyStats {},
+        };
+        assert_eq!(
+            query(&deps, msg).unwrap_err(),
+            StdError::generic_err("forbidden permit lacks permission")
+        );
+        let stats = permit::tests::sign_permit(&key, "stats", contract, vec![Permission::Stats]);
+        let msg = QueryMsg::WithPermit {
+            permit: stats,
+            query: PermitQuery::MyStats {},
+        };
+        let value: PlayerStats = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(value.games_joined, 1);
+        assert_eq!(value.games_finished, 1);
+        assert_eq!(value.wins, 1);
+        assert_eq!(value.total_staked, Uint128(FUNDING_AMOUNT));
+        assert_eq!(value.total_won, Uint128(FUNDING_AMOUNT * 2));
+
+        let env = mock_env(player1.as_str(), &[]);
+        let msg = HandleMsg::RevokePermit {
+            permit_name: "games".to_string(),
+            entropy: None,
+            padding: None,
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+        let msg = QueryMsg::WithPermit {
+            permit: history,
+            query: PermitQuery::MyGames {
+                start_after: None,
+                limit: None,
+            },
+        };
+        assert_eq!(
+            query(&deps, msg).unwrap_err(),
+            StdError::generic_err("forbidden permit revoked")
+        );
     }
 
     #[test]
@@ -662,7 +5424,12 @@ mod tests {
         let _res = init(&mut deps, env, msg).unwrap();
 
         let env = mock_env("player1", &coins(FUNDING_AMOUNT - 1, "uscrt"));
-        let msg = HandleMsg::JoinGame { locator: loc(1) };
+        let msg = HandleMsg::JoinGame {
+            locator: loc(1),
+            free_play: false,
+            entropy: None,
+            padding: None,
+        };
         let _res = handle(&mut deps, env, msg).unwrap_err();
     }
 }
@@ -1,7 +1,14 @@
+pub mod bets;
 pub mod conf;
 pub mod contract;
+pub mod entropy;
 pub mod msg;
+pub mod permit;
+pub mod room;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
 pub mod state;
+pub mod team;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
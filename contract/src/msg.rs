@@ -1,23 +1,230 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use cosmwasm_std::{Binary, Env, HumanAddr, StdError, StdResult, Uint128};
+
+use crate::conf::{
+    MAX_DEADLINE_BLOCKS, MAX_DEADLINE_SECONDS, MIN_DEADLINE_BLOCKS, MIN_DEADLINE_SECONDS,
+    PLAYER_DEADLINE_BLOCKS, PLAYER_INCREMENT_BLOCKS,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InitMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+/**
+ * Every message takes an optional entropy, mixed into the contract's entropy pool,
+ * and an optional padding that is ignored so clients can pad messages to a fixed size.
+ */
 pub enum HandleMsg {
-    JoinGame { locator: String },
-    PrivateGame { locator: String },
-    PlayHand { locator: String, handsign: Handsign },
-    ClaimInactivity { locator: String },
+    JoinGame {
+        locator: String,
+        /// Match with other free players, no stake is taken
+        #[serde(default)]
+        free_play: bool,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    PrivateGame {
+        /// Player's own locator, creator and joiner each have their own
+        locator: String,
+        /// One-time code shared with the opponent, set by the creator and used by the joiner
+        invite: String,
+        /// Stake set by the creator, joiner must match it. Zero for a free game.
+        stake: Option<Uint128>,
+        /// Inactivity deadline set by the creator, ignored for joiner
+        deadline: Option<DeadlinePolicy>,
+        /// Number of games in a best-of series played for the stake, set by the creator
+        series: Option<u8>,
+        /// List the game and its invite in ListOpenPrivateGames while it waits for a player
+        #[serde(default)]
+        discoverable: bool,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    PlayHand {
+        locator: String,
+        handsign: Handsign,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Claims the opponent's inactivity. A game neither player played in time can be aborted by anyone.
+    ClaimInactivity {
+        locator: String,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Claims inactivity on every locator independently, payouts are combined per recipient
+    ClaimMany {
+        locators: Vec<String>,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    PlaceBet {
+        locator: String,
+        side: BetSide,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Stakes again for a rematch of a finished game, new_locator locates the new game
+    OfferRematch {
+        locator: String,
+        new_locator: String,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Stakes again and starts the rematch offered by the opponent
+    AcceptRematch {
+        locator: String,
+        new_locator: String,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Plays against the contract, which matches the stake from the house bankroll
+    PlayHouse {
+        locator: String,
+        stake: Option<Uint128>,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Reveals the house's move and resolves the round of a house game, one block after
+    /// the player played
+    ResolveHouse {
+        locator: String,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Adds sent funds to the house bankroll, admin only
+    FundHouse {
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Takes funds out of the house bankroll, admin only
+    WithdrawHouse {
+        amount: Uint128,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Opens a room for `size` players under a code the other players join with.
+    /// Players play and claim in the room with PlayHand and ClaimInactivity.
+    OpenRoom {
+        locator: String,
+        room: String,
+        size: u8,
+        stake: Option<Uint128>,
+        deadline: Option<DeadlinePolicy>,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Takes a seat in a room and matches its stake, the room starts when it's full
+    JoinRoom {
+        locator: String,
+        room: String,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Opens a game of two teams of two under a room code, the creator takes a seat in `team`
+    OpenTeamGame {
+        locator: String,
+        room: String,
+        team: Team,
+        stake: Option<Uint128>,
+        deadline: Option<DeadlinePolicy>,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Takes a seat in a team and matches the stake, the game starts when both teams are full
+    JoinTeamGame {
+        locator: String,
+        room: String,
+        team: Team,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Lets session_addr play and claim for the player until block height expires_at.
+    /// A past height revokes the key.
+    AuthorizeSessionKey {
+        locator: String,
+        session_addr: HumanAddr,
+        expires_at: u64,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+    /// Stops every query permit of the sender with this name from working
+    RevokePermit {
+        permit_name: String,
+        #[serde(default)]
+        entropy: Option<Binary>,
+        #[serde(default)]
+        padding: Option<String>,
+    },
+}
+
+impl HandleMsg {
+    pub fn entropy(&self) -> Option<&Binary> {
+        match self {
+            HandleMsg::JoinGame { entropy, .. }
+            | HandleMsg::PrivateGame { entropy, .. }
+            | HandleMsg::PlayHand { entropy, .. }
+            | HandleMsg::ClaimInactivity { entropy, .. }
+            | HandleMsg::ClaimMany { entropy, .. }
+            | HandleMsg::PlaceBet { entropy, .. }
+            | HandleMsg::OfferRematch { entropy, .. }
+            | HandleMsg::AcceptRematch { entropy, .. }
+            | HandleMsg::PlayHouse { entropy, .. }
+            | HandleMsg::ResolveHouse { entropy, .. }
+            | HandleMsg::FundHouse { entropy, .. }
+            | HandleMsg::WithdrawHouse { entropy, .. }
+            | HandleMsg::OpenRoom { entropy, .. }
+            | HandleMsg::JoinRoom { entropy, .. }
+            | HandleMsg::OpenTeamGame { entropy, .. }
+            | HandleMsg::JoinTeamGame { entropy, .. }
+            | HandleMsg::AuthorizeSessionKey { entropy, .. }
+            | HandleMsg::RevokePermit { entropy, .. } => entropy.as_ref(),
+        }
+    }
 }
 
 /**
  * Moves that player can make.
  * Size of each handsign must be equal in input so that opponent
- * can't guess player's move from input size.
+ * can't guess player's move from input size. Locators are fixed width for the same reason.
  */
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
 pub enum Handsign {
@@ -32,17 +239,246 @@ impl Handsign {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadlineUnit {
+    Blocks,
+    Seconds,
+}
+
+/**
+ * Chess clock for the players' moves.
+ * Each player starts with a time bank of `duration` which runs while the player owes a move,
+ * and gets `increment` added after each move. Waiting player can claim inactivity
+ * once the other player's bank runs out.
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct DeadlinePolicy {
+    pub unit: DeadlineUnit,
+    pub duration: u64,
+    #[serde(default)]
+    pub increment: u64,
+}
+
+impl DeadlinePolicy {
+    pub fn blocks(duration: u64, increment: u64) -> DeadlinePolicy {
+        DeadlinePolicy {
+            unit: DeadlineUnit::Blocks,
+            duration,
+            increment,
+        }
+    }
+
+    pub fn validate(&self) -> StdResult<()> {
+        let (min, max) = match self.unit {
+            DeadlineUnit::Blocks => (MIN_DEADLINE_BLOCKS, MAX_DEADLINE_BLOCKS),
+            DeadlineUnit::Seconds => (MIN_DEADLINE_SECONDS, MAX_DEADLINE_SECONDS),
+        };
+        if self.duration < min || self.duration > max {
+            return Err(StdError::generic_err(format!(
+                "bad_request deadline must be between {} and {}",
+                min, max
+            )));
+        }
+        if self.increment > max {
+            return Err(StdError::generic_err(format!(
+                "bad_request increment must be at most {}",
+                max
+            )));
+        }
+        Ok(())
+    }
+
+    /// Current block height or time in the unit of the policy
+    pub fn now(&self, env: &Env) -> u64 {
+        match self.unit {
+            DeadlineUnit::Blocks => env.block.height,
+            DeadlineUnit::Seconds => env.block.time,
+        }
+    }
+}
+
+impl Default for DeadlinePolicy {
+    fn default() -> Self {
+        DeadlinePolicy::blocks(PLAYER_DEADLINE_BLOCKS, PLAYER_INCREMENT_BLOCKS)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EndReason {
+    /// Winner reached the number of wins to finish
+    WinsReached,
+    /// Winner claimed opponent's inactivity
+    Inactivity,
+    /// Neither player played in time, stakes and bets were returned
+    Aborted,
+}
+
+/// How a finished game ended and who was paid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameOutcome {
+    pub winner: Option<HumanAddr>,
+    pub reason: EndReason,
+    pub payout: Uint128,
+    pub end_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Team {
+    A,
+    B,
+}
+
+impl Team {
+    pub fn other(&self) -> Team {
+        match self {
+            Team::A => Team::B,
+            Team::B => Team::A,
+        }
+    }
+}
+
+/// How a finished team game ended, payout is paid to each member of the winning team
+/// or refunded to every player of an aborted game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TeamOutcome {
+    pub winner: Option<Team>,
+    pub reason: EndReason,
+    pub payout: Uint128,
+    pub end_height: u64,
+}
+
+/// Player that a spectator backs
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BetSide {
+    Player1,
+    Player2,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GameLobby { locator: String },
-    GameStatus { locator: String },
+    GameLobby {
+        locator: String,
+    },
+    GameStatus {
+        locator: String,
+    },
+    Bets {
+        locator: String,
+    },
+    /// Game status from the perspective of the locator's player.
+    /// Queries can't see the chain clock, current height and time are needed to tell if a claim is possible.
+    /// Rooms and team games answer with RoomStatus and TeamGameStatus instead.
+    MyGameStatus {
+        locator: String,
+        height: Option<u64>,
+        time: Option<u64>,
+    },
+    SeriesStatus {
+        locator: String,
+    },
+    /// Pages through all games by creation order
+    ListGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        status_filter: Option<GameFilter>,
+    },
+    /// Pages through discoverable private games waiting for a player
+    ListOpenPrivateGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    ContractStats {},
+    House {},
+    RoomStatus {
+        locator: String,
+    },
+    TeamGameStatus {
+        locator: String,
+    },
+    /// Queries for the address that signed the permit, no locator needed
+    WithPermit {
+        permit: Permit,
+        query: PermitQuery,
+    },
+    /// Whether address can claim the game of the locator, address may hold a session key
+    /// or be the admin claiming for the house. Any address can abort a game both players
+    /// abandoned. Current height or time is needed to tell if the claim is possible now,
+    /// and height to check a session key.
+    Claimability {
+        locator: String,
+        address: HumanAddr,
+        height: Option<u64>,
+        time: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PermitQuery {
+    /// Games the signer joined in the order joined, needs the history permission
+    MyGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Needs the stats permission
+    MyStats {},
+}
+
+/// SNIP-24 query permit the player signs offline with their wallet key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: PermitSignature,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitParams {
+    /// Contracts the permit works with, must include this contract
+    pub allowed_tokens: Vec<HumanAddr>,
+    pub permit_name: String,
+    pub chain_id: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitSignature {
+    pub pub_key: PubKey,
+    pub signature: Binary,
+}
+
+/// Compressed secp256k1 public key of the signer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PubKey {
+    pub r#type: String,
+    pub value: Binary,
+}
+
+/// What a permit lets its holder query, owner allows everything
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    History,
+    Stats,
+    Owner,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameFilter {
+    Active,
+    Finished,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GameLobbyResponse {
     pub game_started: bool,
     pub player1_locator: bool,
+    pub stake: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -52,6 +488,384 @@ pub struct GameStatusResponse {
     pub player2_played: bool,
     pub player1_wins: u8,
     pub player2_wins: u8,
+    /// Earliest deadline of players who haven't played this round.
+    /// Block height or time in seconds depending on deadline_unit.
     pub deadline: u64,
+    pub deadline_unit: DeadlineUnit,
+    /// Height or time when the current round started and both clocks started running
+    pub round_start: u64,
+    pub player1_time_bank: u64,
+    pub player2_time_bank: u64,
+    /// Deadline for the player's move, none if the player has already played this round
+    pub player1_deadline: Option<u64>,
+    pub player2_deadline: Option<u64>,
     pub game_over: bool,
+    pub stake: Uint128,
+    pub outcome: Option<GameOutcome>,
+    pub rematch: Option<RematchStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeriesStatusResponse {
+    pub games: u8,
+    pub player1_wins: u8,
+    pub player2_wins: u8,
+    /// Stake of each player for the whole series
+    pub stake: Uint128,
+    /// Outcomes of finished games in the series
+    pub results: Vec<GameOutcome>,
+    pub outcome: Option<GameOutcome>,
+    pub current_game: GameStatusResponse,
+}
+
+/// Rematch of a finished game, players find the new game with the new locators they chose
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RematchStatus {
+    pub offered_by: HumanAddr,
+    pub started: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    WaitingForOpponentJoin,
+    WaitingForMe,
+    WaitingForOpponent,
+    Claimable,
+    /// Both players ran out of time, claiming inactivity refunds the stakes
+    Abandoned,
+    Finished,
+    Canceled,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PlayerStatus {
+    pub wins: u8,
+    pub played: bool,
+    pub can_play: bool,
+    pub can_claim_inactivity: bool,
+    pub time_bank: u64,
+    pub deadline: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MyGameStatusResponse {
+    pub phase: GamePhase,
+    pub round: u8,
+    pub me: PlayerStatus,
+    pub opponent: PlayerStatus,
+    pub opponent_address: Option<HumanAddr>,
+    pub deadline_unit: DeadlineUnit,
+    pub stake: Uint128,
+    pub outcome: Option<GameOutcome>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimabilityResponse {
+    pub can_claim: bool,
+    /// Height or time the claim opens at, 0 for a lobby refund, none while the address can't claim
+    pub claimable_from: Option<u64>,
+    pub deadline_unit: DeadlineUnit,
+    /// Amount the claim pays to the player
+    pub payout: Uint128,
+    /// Both players ran out of time, the claim aborts the game and refunds each player's stake.
+    /// Anyone can abort, payout is zero for addresses that aren't players
+    pub abort: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoomPlayerStatus {
+    pub address: HumanAddr,
+    pub played: bool,
+    pub eliminated: bool,
+    pub time_bank: u64,
+    /// Deadline for the player's move, none if the player has played this round or is out
+    pub deadline: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoomStatusResponse {
+    pub size: u8,
+    pub started: bool,
+    pub round: u16,
+    pub players: Vec<RoomPlayerStatus>,
+    pub deadline_unit: DeadlineUnit,
+    pub round_start: u64,
+    pub stake: Uint128,
+    pub game_over: bool,
+    pub outcome: Option<GameOutcome>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TeamPlayerStatus {
+    pub address: HumanAddr,
+    pub played: bool,
+    pub time_bank: u64,
+    /// Deadline for the player's move, none if their duel of this round needs nothing from them
+    pub deadline: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TeamGameStatusResponse {
+    pub started: bool,
+    pub round: u16,
+    /// Players in seat order, first players of the teams duel each other and so do the second
+    pub team_a: Vec<TeamPlayerStatus>,
+    pub team_b: Vec<TeamPlayerStatus>,
+    pub team_a_wins: u8,
+    pub team_b_wins: u8,
+    pub deadline_unit: DeadlineUnit,
+    pub round_start: u64,
+    pub stake: Uint128,
+    pub game_over: bool,
+    pub outcome: Option<TeamOutcome>,
+}
+
+/// Result of one claim in ClaimMany, error is the reason a failed claim was skipped
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimResult {
+    pub locator: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// JSON-encoded in HandleResponse.data, one variant for each HandleMsg
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAnswer {
+    /// Game started when an opponent was waiting, otherwise the player waits as player1
+    JoinGame {
+        game_started: bool,
+        player1: bool,
+    },
+    PrivateGame {
+        game_started: bool,
+        player1: bool,
+    },
+    /// Round the move was played in and the score after it
+    PlayHand {
+        round_resolved: bool,
+        round: u8,
+        wins: u8,
+        opponent_wins: u8,
+        game_over: bool,
+    },
+    PlayRoom {
+        round_resolved: bool,
+        round: u16,
+        standing: u8,
+        eliminated: bool,
+        game_over: bool,
+    },
+    PlayTeamGame {
+        round_resolved: bool,
+        round: u16,
+        team_wins: u8,
+        other_team_wins: u8,
+        game_over: bool,
+    },
+    /// Refunded when a waiting player left or the game was aborted, game over is false when a series goes on
+    ClaimInactivity {
+        refunded: bool,
+        game_over: bool,
+    },
+    /// Results are in the order of the locators
+    ClaimMany {
+        results: Vec<ClaimResult>,
+    },
+    PlaceBet {
+        side_total: Uint128,
+    },
+    /// Seat of the sender in the rematch
+    OfferRematch {
+        player1: bool,
+    },
+    AcceptRematch {
+        player1: bool,
+    },
+    PlayHouse {
+        stake: Uint128,
+    },
+    ResolveHouse {
+        round: u8,
+        house_handsign: Handsign,
+        wins: u8,
+        house_wins: u8,
+        game_over: bool,
+    },
+    FundHouse {
+        bankroll: Uint128,
+    },
+    WithdrawHouse {
+        bankroll: Uint128,
+    },
+    OpenRoom {
+        size: u8,
+    },
+    JoinRoom {
+        started: bool,
+        players: u8,
+    },
+    OpenTeamGame {
+        started: bool,
+    },
+    JoinTeamGame {
+        started: bool,
+    },
+    AuthorizeSessionKey {
+        active: bool,
+    },
+    RevokePermit {
+        permit_name: String,
+    },
+}
+
+/**
+ * Leading word of error messages that clients can match on, the rest of the message
+ * is a human readable reason. Other errors are plain text.
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Malformed message, such as a bad locator or stake
+    BadRequest,
+    /// Message not allowed in the current state of the game
+    Forbidden,
+    /// Handsign was already played this round
+    AlreadyPlayed,
+    /// Game has ended
+    GameOver,
+    /// Sent funds don't cover the stake, bet or deposit
+    InsufficientFunds,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BetsResponse {
+    pub player1_total: Uint128,
+    pub player2_total: Uint128,
+    pub open: bool,
+}
+
+/// Running totals over the whole contract
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct ContractStats {
+    pub games_created: u64,
+    pub games_finished: u64,
+    pub public_games: u64,
+    pub private_games: u64,
+    pub house_games: u64,
+    pub room_games: u64,
+    pub team_games: u64,
+    pub inactivity_claims: u64,
+    pub lobby_cancellations: u64,
+    pub total_staked: Uint128,
+    pub total_paid_out: Uint128,
+    /// Stakes held for lobby entries and unfinished games
+    pub escrowed: Uint128,
+    /// Stakes that went back to the house bankroll from house wins and aborted house games
+    pub returned_to_house: Uint128,
+}
+
+impl ContractStats {
+    pub fn stake(&mut self, amount: Uint128) {
+        self.total_staked += amount;
+        self.escrowed += amount;
+    }
+
+    pub fn pay_out(&mut self, amount: Uint128) {
+        self.total_paid_out += amount;
+        self.escrowed = Uint128(self.escrowed.u128() - amount.u128());
+    }
+
+    /// Moves stakes out of escrow into the house bankroll, nothing is sent out
+    pub fn return_to_house(&mut self, amount: Uint128) {
+        self.returned_to_house += amount;
+        self.escrowed = Uint128(self.escrowed.u128() - amount.u128());
+    }
+}
+
+/// Totals over the games of one player
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PlayerStats {
+    /// Lobby entries and games joined
+    pub games_joined: u64,
+    pub games_finished: u64,
+    pub wins: u64,
+    pub total_staked: Uint128,
+    pub total_won: Uint128,
+    /// Stakes returned from the lobby or from aborted games
+    pub total_refunded: Uint128,
+}
+
+/// Game of the permit signer, found by the locator they joined with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlayerGame {
+    pub index: u64,
+    pub locator: String,
+    pub stake: Uint128,
+    pub started: bool,
+    pub game_over: bool,
+    /// Stake was returned from the lobby
+    pub refunded: bool,
+    /// Whether the player won, none until the game is over or when it had no winner
+    pub won: Option<bool>,
+    pub reason: Option<EndReason>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MyGamesResponse {
+    pub games: Vec<PlayerGame>,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HouseResponse {
+    pub bankroll: Uint128,
+    pub exposure_limit: Uint128,
+    /// Stakes the house put up in games started at exposure_height
+    pub exposure: Uint128,
+    pub exposure_height: u64,
+}
+
+/// Public metadata of a game, id is hashed so that it doesn't reveal a locator
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameSummary {
+    pub index: u64,
+    pub id: String,
+    pub round: u16,
+    /// Players the game started with, more than 2 for rooms which have no per-player wins
+    pub players: u8,
+    pub player1_wins: u8,
+    pub player2_wins: u8,
+    pub deadline: u64,
+    pub deadline_unit: DeadlineUnit,
+    pub stake: Uint128,
+    pub game_over: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListGamesResponse {
+    pub games: Vec<GameSummary>,
+    /// Pass as start_after to continue, none when nothing was left to scan
+    pub last_scanned: Option<u64>,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OpenPrivateGame {
+    pub index: u64,
+    pub id: String,
+    /// Code to join with, listed games are open to anyone
+    pub invite: String,
+    pub stake: Uint128,
+    pub deadline: DeadlinePolicy,
+    pub series: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListOpenPrivateGamesResponse {
+    pub games: Vec<OpenPrivateGame>,
+    /// Pass as start_after to continue, none when nothing was left to scan
+    pub last_scanned: Option<u64>,
+    pub total: u64,
 }
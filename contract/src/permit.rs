@@ -0,0 +1,271 @@
+use bech32::ToBase32;
+use ripemd160::Ripemd160;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{to_vec, HumanAddr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{prefixed, prefixed_read, typed, typed_read};
+
+use crate::conf::ADDRESS_PREFIX;
+use crate::msg::{Permission, Permit};
+
+/**
+ * Document the wallet signs for a permit, an amino sign doc with a single message.
+ * Fields are in alphabetical order as the signed JSON is canonical.
+ */
+#[derive(Serialize)]
+struct SignedPermit<'a> {
+    account_number: Uint128,
+    chain_id: &'a str,
+    fee: Fee,
+    memo: &'a str,
+    msgs: Vec<PermitMsg<'a>>,
+    sequence: Uint128,
+}
+
+#[derive(Serialize)]
+struct Fee {
+    amount: Vec<FeeCoin>,
+    gas: Uint128,
+}
+
+#[derive(Serialize)]
+struct FeeCoin {
+    amount: Uint128,
+    denom: &'static str,
+}
+
+#[derive(Serialize)]
+struct PermitMsg<'a> {
+    r#type: &'static str,
+    value: PermitContent<'a>,
+}
+
+#[derive(Serialize)]
+struct PermitContent<'a> {
+    allowed_tokens: &'a [HumanAddr],
+    permissions: &'a [Permission],
+    permit_name: &'a str,
+}
+
+/// Bytes the wallet signed for the permit's params
+fn signed_bytes(permit: &Permit) -> StdResult<Vec<u8>> {
+    let params = &permit.params;
+    to_vec(&SignedPermit {
+        account_number: Uint128(0),
+        chain_id: &params.chain_id,
+        fee: Fee {
+            amount: vec![FeeCoin {
+                amount: Uint128(0),
+                denom: "uscrt",
+            }],
+            gas: Uint128(1),
+        },
+        memo: "",
+        msgs: vec![PermitMsg {
+            r#type: "query_permit",
+            value: PermitContent {
+                allowed_tokens: &params.allowed_tokens,
+                permissions: &params.permissions,
+                permit_name: &params.permit_name,
+            },
+        }],
+        sequence: Uint128(0),
+    })
+}
+
+/// Bech32 account address of a compressed secp256k1 public key
+pub fn address(pub_key: &[u8]) -> StdResult<HumanAddr> {
+    let hash = Ripemd160::digest(&Sha256::digest(pub_key));
+    bech32::encode(ADDRESS_PREFIX, hash.to_base32())
+        .map(HumanAddr)
+        .map_err(|_| StdError::generic_err("bad_request invalid permit public key"))
+}
+
+/**
+ * Checks the permit's signature and returns the address that signed it.
+ * The API of the cosmwasm-std this contract builds against has no signature
+ * verification, so the secp256k1 signature is verified in the contract.
+ */
+pub fn validate<S: Storage>(
+    storage: &S,
+    permit: &Permit,
+    contract: &HumanAddr,
+) -> StdResult<HumanAddr> {
+    if !permit.params.allowed_tokens.contains(contract) {
+        return Err(StdError::generic_err(
+            "forbidden permit not for this contract",
+        ));
+    }
+    let pub_key = libsecp256k1::PublicKey::parse_slice(
+        permit.signature.pub_key.value.as_slice(),
+        Some(libsecp256k1::PublicKeyFormat::Compressed),
+    )
+    .map_err(|_| StdError::generic_err("bad_request invalid permit public key"))?;
+    let signature =
+        libsecp256k1::Signature::parse_standard_slice(permit.signature.signature.as_slice())
+            .map_err(|_| StdError::generic_err("bad_request invalid permit signature"))?;
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(&signed_bytes(permit)?));
+    let message = libsecp256k1::Message::parse(&hash);
+    if !libsecp256k1::verify(&message, &signature, &pub_key) {
+        return Err(StdError::generic_err("forbidden invalid permit signature"));
+    }
+
+    let signer = address(&pub_key.serialize_compressed())?;
+    if is_revoked(storage, &signer, &permit.params.permit_name)? {
+        return Err(StdError::generic_err("forbidden permit revoked"));
+    }
+    Ok(signer)
+}
+
+/// Checks that the permit grants the permission, owner grants them all
+pub fn require(permit: &Permit, permission: Permission) -> StdResult<()> {
+    let permissions = &permit.params.permissions;
+    if permissions.contains(&permission) || permissions.contains(&Permission::Owner) {
+        return Ok(());
+    }
+    Err(StdError::generic_err("forbidden permit lacks permission"))
+}
+
+pub fn revoke<S: Storage>(storage: &mut S, signer: &HumanAddr, permit_name: &str) {
+    let mut space = prefixed(b"revoked_permit", storage);
+    let mut bucket = typed::<_, bool>(&mut space);
+    bucket
+        .save(&revoked_key(signer, permit_name), &true)
+        .unwrap();
+}
+
+pub fn is_revoked<S: Storage>(
+    storage: &S,
+    signer: &HumanAddr,
+    permit_name: &str,
+) -> StdResult<bool> {
+    let space = prefixed_read(b"revoked_permit", storage);
+    let bucket = typed_read::<_, bool>(&space);
+    Ok(bucket
+        .may_load(&revoked_key(signer, permit_name))?
+        .unwrap_or(false))
+}
+
+/// Length of the address separates it from the permit name
+fn revoked_key(signer: &HumanAddr, permit_name: &str) -> Vec<u8> {
+    let signer = signer.as_str().as_bytes();
+    [
+        &(signer.len() as u32).to_be_bytes(),
+        signer,
+        permit_name.as_bytes(),
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::msg::{PermitParams, PermitSignature, PubKey};
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Binary;
+
+    /// Signs a permit the way a wallet does, for tests of permit queries
+    pub fn sign_permit(
+        key: &[u8; 32],
+        name: &str,
+        contract: &str,
+        permissions: Vec<Permission>,
+    ) -> Permit {
+        let secret = libsecp256k1::SecretKey::parse(key).unwrap();
+        let pub_key = libsecp256k1::PublicKey::from_secret_key(&secret);
+        let mut permit = Permit {
+            params: PermitParams {
+                allowed_tokens: vec![HumanAddr::from(contract)],
+                permit_name: name.to_string(),
+                chain_id: "secret-4".to_string(),
+                permissions,
+            },
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: Binary(pub_key.serialize_compressed().to_vec()),
+                },
+                signature: Binary(vec![]),
+            },
+        };
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Sha256::digest(&signed_bytes(&permit).unwrap()));
+        let (signature, _) = libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret);
+        permit.signature.signature = Binary(signature.serialize().to_vec());
+        permit
+    }
+
+    pub fn signer(key: &[u8; 32]) -> HumanAddr {
+        let secret = libsecp256k1::SecretKey::parse(key).unwrap();
+        address(&libsecp256k1::PublicKey::from_secret_key(&secret).serialize_compressed()).unwrap()
+    }
+
+    #[test]
+    fn signed_doc_is_canonical_amino_json() {
+        let permit = sign_permit(
+            &[1u8; 32],
+            "games",
+            "secret1contract",
+            vec![Permission::History],
+        );
+        assert_eq!(
+            String::from_utf8(signed_bytes(&permit).unwrap()).unwrap(),
+            concat!(
+                r#"{"account_number":"0","chain_id":"secret-4","fee":{"amount":[{"amount":"0","denom":"uscrt"}],"gas":"1"},"#,
+                r#""memo":"","msgs":[{"type":"query_permit","value":{"allowed_tokens":["secret1contract"],"#,
+                r#""permissions":["history"],"permit_name":"games"}}],"sequence":"0"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn address_is_bech32_of_key_hash() {
+        // public key of secret key 0x0101..01, address computed independently
+        let pub_key =
+            hex::decode("031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f")
+                .unwrap();
+        assert_eq!(
+            address(&pub_key).unwrap(),
+            HumanAddr::from("secret10xcqpzrky6eff2g52qdye53xkk9jxkvrr9w4al")
+        );
+        assert_eq!(signer(&[1u8; 32]), address(&pub_key).unwrap());
+    }
+
+    #[test]
+    fn validates_signature_and_revocation() {
+        let mut storage = MockStorage::new();
+        let contract = HumanAddr::from("secret1contract");
+        let permit = sign_permit(
+            &[7u8; 32],
+            "games",
+            contract.as_str(),
+            vec![Permission::Stats],
+        );
+        let signer = validate(&storage, &permit, &contract).unwrap();
+        assert_eq!(signer, tests::signer(&[7u8; 32]));
+
+        assert_eq!(
+            validate(&storage, &permit, &HumanAddr::from("secret1other")).unwrap_err(),
+            StdError::generic_err("forbidden permit not for this contract")
+        );
+        let mut tampered = permit.clone();
+        tampered.params.permissions = vec![Permission::Owner];
+        assert_eq!(
+            validate(&storage, &tampered, &contract).unwrap_err(),
+            StdError::generic_err("forbidden invalid permit signature")
+        );
+
+        require(&permit, Permission::Stats).unwrap();
+        require(&permit, Permission::History).unwrap_err();
+
+        revoke(&mut storage, &signer, "other");
+        validate(&storage, &permit, &contract).unwrap();
+        revoke(&mut storage, &signer, "games");
+        assert_eq!(
+            validate(&storage, &permit, &contract).unwrap_err(),
+            StdError::generic_err("forbidden permit revoked")
+        );
+    }
+}
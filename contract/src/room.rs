@@ -0,0 +1,389 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{prefixed, prefixed_read, typed, typed_read};
+
+use crate::msg::{DeadlinePolicy, EndReason, GameOutcome, Handsign};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoomPlayer {
+    pub address: HumanAddr,
+    pub locator: [u8; 32],
+    pub handsign: Option<Handsign>,
+    pub time_bank: u64,
+    pub eliminated: bool,
+}
+
+/// Game of 3 or more players, each round eliminates the players whose handsign was beaten
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Room {
+    #[serde(skip)]
+    id: [u8; 32],
+    /// Number of players the room starts with
+    pub size: u8,
+    /// Amount staked by each player, last player standing takes all
+    pub stake: Uint128,
+    pub deadline: DeadlinePolicy,
+    pub players: Vec<RoomPlayer>,
+    pub started: bool,
+    pub round: u16,
+    /// Height or time in the unit of the deadline policy when the current round started
+    pub round_start: u64,
+    pub outcome: Option<GameOutcome>,
+}
+
+impl Room {
+    pub fn new(id: [u8; 32], size: u8, stake: Uint128, deadline: DeadlinePolicy) -> Room {
+        Room {
+            id: id,
+            size: size,
+            stake: stake,
+            deadline: deadline,
+            players: vec![],
+            started: false,
+            round: 1,
+            round_start: 0,
+            outcome: None,
+        }
+    }
+
+    /// Seats a player, the room starts at `now` once it's full
+    pub fn seat(&mut self, address: HumanAddr, locator: [u8; 32], now: u64) -> StdResult<()> {
+        if self.started {
+            return Err(StdError::generic_err("forbidden room already started"));
+        }
+        if self.players.iter().any(|p| p.address == address) {
+            return Err(StdError::generic_err("forbidden already in room"));
+        }
+        self.players.push(RoomPlayer {
+            address,
+            locator,
+            handsign: None,
+            time_bank: self.deadline.duration,
+            eliminated: false,
+        });
+        if self.players.len() == self.size as usize {
+            self.started = true;
+            self.round_start = now;
+        }
+        Ok(())
+    }
+
+    /// Takes a player out of a room that hasn't started
+    pub fn unseat(&mut self, address: &HumanAddr) -> StdResult<RoomPlayer> {
+        if self.started {
+            return Err(StdError::generic_err("forbidden room already started"));
+        }
+        match self.players.iter().position(|p| p.address == *address) {
+            Some(i) => Ok(self.players.remove(i)),
+            None => Err(StdError::generic_err("You are not a player")),
+        }
+    }
+
+    pub fn standing(&self) -> impl Iterator<Item = &RoomPlayer> {
+        self.players.iter().filter(|p| !p.eliminated)
+    }
+
+    /// Height or time at which the player runs out of time, none if they have played this round
+    pub fn player_deadline(&self, player: &RoomPlayer) -> Option<u64> {
+        match (player.eliminated, player.handsign) {
+            (false, None) => Some(self.round_start + player.time_bank),
+            _ => None,
+        }
+    }
+
+    /// Plays the player's handsign at `now`, the round resolves once every standing player has played
+    pub fn play(&mut self, address: &HumanAddr, handsign: Handsign, now: u64) -> StdResult<()> {
+        let elapsed = now.saturating_sub(self.round_start);
+        let increment = self.deadline.increment;
+        let player = match self
+            .players
+            .iter_mut()
+            .find(|p| p.address == *address && !p.eliminated)
+        {
+            Some(player) => player,
+            None => return Err(StdError::generic_err("You are not a player")),
+        };
+        if player.handsign.is_some() {
+            return Err(StdError::generic_err("already_played"));
+        }
+        player.handsign = Some(handsign);
+        player.time_bank = player.time_bank.saturating_sub(elapsed) + increment;
+        self.resolve(now);
+        Ok(())
+    }
+
+    /**
+     * Eliminates standing players who haven't played by their deadline.
+     * Only a player who has played this round can claim. Returns the number eliminated.
+     */
+    pub fn time_out(&mut self, address: &HumanAddr, now: u64) -> StdResult<usize> {
+        match self.standing().find(|p| p.address == *address) {
+            Some(p) if p.handsign.is_some() => {}
+            Some(_) => return Err(StdError::generic_err("unable to claim inactivity")),
+            None => return Err(StdError::generic_err("You are not a player")),
+        }
+        let expired: Vec<usize> = (0..self.players.len())
+            .filter(|i| match self.player_deadline(&self.players[*i]) {
+                Some(deadline) => now >= deadline,
+                None => false,
+            })
+            .collect();
+        if expired.is_empty() {
+            return Err(StdError::generic_err(
+                "under deadline for claiming inactivity",
+            ));
+        }
+        for i in &expired {
+            self.players[*i].eliminated = true;
+        }
+        self.resolve(now);
+        Ok(expired.len())
+    }
+
+    /// Every standing player ran out of time without playing, nobody can claim the others' stakes
+    pub fn abandoned(&self, now: u64) -> bool {
+        self.started
+            && self.outcome.is_none()
+            && self.standing().all(|p| {
+                self.player_deadline(p)
+                    .map_or(false, |deadline| now >= deadline)
+            })
+    }
+
+    /// Earliest deadline among standing players, round start once all of them have played
+    pub fn deadline(&self) -> u64 {
+        self.standing()
+            .filter_map(|p| self.player_deadline(p))
+            .min()
+            .unwrap_or(self.round_start)
+    }
+
+    /**
+     * Ends the round once every standing player has played.
+     * Players showing the beaten sign are eliminated when exactly two signs are present,
+     * a single sign or all three signs replay the round.
+     */
+    fn resolve(&mut self, now: u64) {
+        if self.standing().any(|p| p.handsign.is_none()) {
+            return;
+        }
+        let mut signs: Vec<Handsign> = vec![];
+        for p in self.standing() {
+            let sign = p.handsign.unwrap();
+            if !signs.contains(&sign) {
+                signs.push(sign);
+            }
+        }
+        if signs.len() == 2 {
+            let beaten = if signs[0].beats(signs[1]) {
+                signs[1]
+            } else {
+                signs[0]
+            };
+            for p in self.players.iter_mut() {
+                if p.handsign == Some(beaten) {
+                    p.eliminated = true;
+                }
+            }
+        }
+        for p in self.players.iter_mut() {
+            p.handsign = None;
+        }
+        self.round += 1;
+        self.round_start = now;
+    }
+
+    /// Last player standing once everyone else has been eliminated
+    pub fn winner(&self) -> Option<&RoomPlayer> {
+        let mut standing = self.standing();
+        match (standing.next(), standing.next()) {
+            (Some(p), None) => Some(p),
+            _ => None,
+        }
+    }
+
+    /// Ends the game with winner taking all stakes, returns the amount to pay out
+    pub fn finish(&mut self, winner: HumanAddr, reason: EndReason, height: u64) -> Uint128 {
+        let payout = Uint128(self.stake.u128() * self.players.len() as u128);
+        self.outcome = Some(GameOutcome {
+            winner: Some(winner),
+            reason,
+            payout,
+            end_height: height,
+        });
+        payout
+    }
+
+    /**
+     * Ends an abandoned room with no winner, standing players get their stakes back and share
+     * the stakes of eliminated players. Returns each standing player's payout.
+     */
+    pub fn abort(&mut self, height: u64) -> Vec<(HumanAddr, Uint128)> {
+        let total = self.stake.u128() * self.players.len() as u128;
+        let standing: Vec<HumanAddr> = self.standing().map(|p| p.address.clone()).collect();
+        let share = total / standing.len() as u128;
+        let remainder = total - share * standing.len() as u128;
+        self.outcome = Some(GameOutcome {
+            winner: None,
+            reason: EndReason::Aborted,
+            payout: Uint128(total),
+            end_height: height,
+        });
+        standing
+            .into_iter()
+            .enumerate()
+            .map(|(i, address)| match i {
+                0 => (address, Uint128(share + remainder)),
+                _ => (address, Uint128(share)),
+            })
+            .collect()
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) {
+        let mut space = prefixed(b"room", storage);
+        let mut bucket = typed::<_, Room>(&mut space);
+        bucket.save(&self.id, &self).unwrap();
+    }
+
+    pub fn load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Self> {
+        let mut space = prefixed_read(b"room", storage);
+        let bucket = typed_read::<_, Room>(&mut space);
+        let room = bucket.load(&id)?;
+        Ok(Self { id, ..room })
+    }
+
+    pub fn may_load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Option<Self>> {
+        let mut space = prefixed_read(b"room", storage);
+        let bucket = typed_read::<_, Room>(&mut space);
+        bucket
+            .may_load(&id)
+            .map(|maybe| maybe.map(|data| Self { id, ..data }))
+    }
+}
+
+/// Room id for the code players join with
+pub fn save_room_code<S: Storage>(storage: &mut S, code: [u8; 32], room: [u8; 32]) {
+    let mut space = prefixed(b"room_code", storage);
+    let mut bucket = typed::<_, [u8; 32]>(&mut space);
+    bucket.save(&code, &room).unwrap();
+}
+
+pub fn may_load_room_code<S: Storage>(storage: &S, code: [u8; 32]) -> StdResult<Option<[u8; 32]>> {
+    let space = prefixed_read(b"room_code", storage);
+    let bucket = typed_read::<_, [u8; 32]>(&space);
+    bucket.may_load(&code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Handsign::{PAPR, ROCK, SCRS};
+
+    fn room(size: u8) -> Room {
+        let mut room = Room::new([0u8; 32], size, Uint128(10), DeadlinePolicy::blocks(10, 5));
+        for i in 0..size {
+            room.seat(HumanAddr::from(format!("p{}", i)), [i; 32], 100)
+                .unwrap();
+        }
+        room
+    }
+
+    fn play(room: &mut Room, signs: &[Handsign], now: u64) {
+        let players: Vec<HumanAddr> = room.standing().map(|p| p.address.clone()).collect();
+        for (address, sign) in players.iter().zip(signs) {
+            room.play(address, *sign, now).unwrap();
+        }
+    }
+
+    fn standing(room: &Room) -> Vec<String> {
+        room.standing().map(|p| p.address.to_string()).collect()
+    }
+
+    #[test]
+    fn starts_when_full() {
+        let mut room = Room::new([0u8; 32], 3, Uint128(10), DeadlinePolicy::default());
+        room.seat(HumanAddr::from("a"), [1u8; 32], 5).unwrap();
+        assert_eq!(
+            room.seat(HumanAddr::from("a"), [2u8; 32], 5).unwrap_err(),
+            StdError::generic_err("forbidden already in room")
+        );
+        room.seat(HumanAddr::from("b"), [2u8; 32], 5).unwrap();
+        assert_eq!(room.started, false);
+        room.unseat(&HumanAddr::from("b")).unwrap();
+        room.seat(HumanAddr::from("b"), [2u8; 32], 6).unwrap();
+        room.seat(HumanAddr::from("c"), [3u8; 32], 7).unwrap();
+        assert_eq!(room.started, true);
+        assert_eq!(room.round_start, 7);
+        assert!(room.unseat(&HumanAddr::from("c")).is_err());
+    }
+
+    #[test]
+    fn beaten_signs_eliminated() {
+        let mut room = room(4);
+        play(&mut room, &[ROCK, SCRS, ROCK, SCRS], 101);
+        assert_eq!(standing(&room), vec!["p0", "p2"]);
+        assert_eq!(room.round, 2);
+        assert_eq!(room.winner(), None);
+
+        play(&mut room, &[PAPR, SCRS], 102);
+        assert_eq!(room.winner().unwrap().address, HumanAddr::from("p2"));
+    }
+
+    #[test]
+    fn all_or_no_signs_differ_replays() {
+        let mut room = room(4);
+        play(&mut room, &[ROCK, PAPR, SCRS, ROCK], 101);
+        assert_eq!(standing(&room).len(), 4);
+        play(&mut room, &[PAPR, PAPR, PAPR, PAPR], 102);
+        assert_eq!(standing(&room).len(), 4);
+        assert_eq!(room.round, 3);
+        assert!(room.players.iter().all(|p| p.handsign.is_none()));
+    }
+
+    #[test]
+    fn inactive_players_time_out() {
+        let mut room = room(3);
+        room.play(&HumanAddr::from("p0"), ROCK, 101).unwrap();
+        room.play(&HumanAddr::from("p1"), SCRS, 102).unwrap();
+        assert_eq!(
+            room.time_out(&HumanAddr::from("p2"), 200).unwrap_err(),
+            StdError::generic_err("unable to claim inactivity")
+        );
+        assert_eq!(
+            room.time_out(&HumanAddr::from("p0"), 109).unwrap_err(),
+            StdError::generic_err("under deadline for claiming inactivity")
+        );
+        // p2 times out and the round between p0 and p1 resolves
+        assert_eq!(room.time_out(&HumanAddr::from("p0"), 110).unwrap(), 1);
+        assert_eq!(room.winner().unwrap().address, HumanAddr::from("p0"));
+    }
+
+    #[test]
+    fn abandoned_room_aborts() {
+        let mut room = room(5);
+        play(&mut room, &[ROCK, SCRS, ROCK, ROCK, SCRS], 101);
+        assert_eq!(standing(&room), vec!["p0", "p2", "p3"]);
+        assert_eq!(room.deadline(), 115);
+        assert_eq!(room.abandoned(114), false);
+        assert_eq!(room.abandoned(115), true);
+
+        // a player who played can still claim the others' inactivity
+        let mut played = room.clone();
+        played.play(&HumanAddr::from("p0"), ROCK, 102).unwrap();
+        assert_eq!(played.abandoned(200), false);
+
+        // standing players share the eliminated players' stakes, the first one takes the remainder
+        let payouts = room.abort(115);
+        assert_eq!(
+            payouts,
+            vec![
+                (HumanAddr::from("p0"), Uint128(18)),
+                (HumanAddr::from("p2"), Uint128(16)),
+                (HumanAddr::from("p3"), Uint128(16)),
+            ]
+        );
+        assert_eq!(room.outcome.unwrap().reason, EndReason::Aborted);
+    }
+}
@@ -0,0 +1,71 @@
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::msg::{
+    BetsResponse, ClaimabilityResponse, ContractStats, ErrorCode, GameLobbyResponse,
+    GameStatusResponse, HandleAnswer, HandleMsg, HouseResponse, InitMsg, ListGamesResponse,
+    ListOpenPrivateGamesResponse, MyGameStatusResponse, MyGamesResponse, PlayerStats, QueryMsg,
+    RoomStatusResponse, SeriesStatusResponse, TeamGameStatusResponse,
+};
+
+/// Schemas of the contract API, written to schema/ by `cargo run --example schema`
+pub fn schemas() -> Vec<RootSchema> {
+    vec![
+        schema_for!(InitMsg),
+        schema_for!(HandleMsg),
+        schema_for!(QueryMsg),
+        schema_for!(HandleAnswer),
+        schema_for!(ErrorCode),
+        schema_for!(GameLobbyResponse),
+        schema_for!(GameStatusResponse),
+        schema_for!(MyGameStatusResponse),
+        schema_for!(SeriesStatusResponse),
+        schema_for!(BetsResponse),
+        schema_for!(ContractStats),
+        schema_for!(HouseResponse),
+        schema_for!(ListGamesResponse),
+        schema_for!(ListOpenPrivateGamesResponse),
+        schema_for!(RoomStatusResponse),
+        schema_for!(TeamGameStatusResponse),
+        schema_for!(ClaimabilityResponse),
+        schema_for!(MyGamesResponse),
+        schema_for!(PlayerStats),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    /// File name export_schema gives a schema, its title in snake case
+    fn file_name(schema: &RootSchema) -> String {
+        let title = schema.schema.metadata.as_ref().unwrap().title.as_ref();
+        let mut name = String::new();
+        for (i, ch) in title.unwrap().char_indices() {
+            if i != 0 && ch.is_uppercase() {
+                name.push('_');
+            }
+            name.push(ch.to_ascii_lowercase());
+        }
+        format!("{}.json", name)
+    }
+
+    #[test]
+    fn schema_up_to_date() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+        let schemas = schemas();
+        for schema in &schemas {
+            let name = file_name(schema);
+            let expected = serde_json::to_string_pretty(schema).unwrap() + "\n";
+            let checked_in = fs::read_to_string(dir.join(&name)).unwrap_or_default();
+            assert!(
+                checked_in == expected,
+                "schema/{} is stale, run `cargo run --example schema`",
+                name
+            );
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), schemas.len());
+    }
+}
@@ -1,10 +1,13 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{HumanAddr, StdResult, Storage};
-use cosmwasm_storage::{prefixed, prefixed_read, singleton, typed, typed_read, Singleton};
+use cosmwasm_std::{Env, HumanAddr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{
+    prefixed, prefixed_read, singleton, singleton_read, typed, typed_read, Singleton,
+};
 
-use crate::msg::Handsign;
+use crate::conf::MAX_HOUSE_EXPOSURE_PER_BLOCK;
+use crate::msg::{ContractStats, DeadlinePolicy, EndReason, GameOutcome, Handsign, PlayerStats};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Locator {
@@ -12,19 +15,65 @@ pub struct Locator {
     id: [u8; 32],
     pub game: [u8; 32],
     pub player: HumanAddr,
-    pub canceled: bool,
+    pub settlement: Settlement,
+    pub stake: Uint128,
+    pub deadline: DeadlinePolicy,
+    /// Number of games in the series a private game creator asked for
+    pub series: Option<u8>,
+}
+
+/// What happened to the stake a locator was created with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Settlement {
+    /// Waiting for an opponent, can be refunded
+    Open,
+    /// In a game, released when the game ends
+    Matched,
+    /// Refunded from the lobby
+    Refunded,
+    /// Released with the result of the game
+    Paid,
 }
 
 impl Locator {
-    pub fn new(id: [u8; 32], game: [u8; 32], player: HumanAddr) -> Locator {
+    pub fn new(
+        id: [u8; 32],
+        game: [u8; 32],
+        player: HumanAddr,
+        stake: Uint128,
+        deadline: DeadlinePolicy,
+    ) -> Locator {
         Locator {
             id: id,
             game: game,
             player: player,
-            canceled: false,
+            settlement: Settlement::Open,
+            stake: stake,
+            deadline: deadline,
+            series: None,
         }
     }
 
+    /// Saves a new locator and adds it to its player's games
+    pub fn create<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        self.save(storage);
+        push_index(storage, &player_index(&self.player), self.id)?;
+        let stake = self.stake;
+        update_player_stats(storage, &self.player, |s| {
+            s.games_joined += 1;
+            s.total_staked += stake;
+        })
+    }
+
+    /// Gives the stake back to a player who left before their game started
+    pub fn refund<S: Storage>(&mut self, storage: &mut S) -> StdResult<()> {
+        self.settlement = Settlement::Refunded;
+        self.save(storage);
+        let stake = self.stake;
+        update_player_stats(storage, &self.player, |s| s.total_refunded += stake)
+    }
+
     pub fn save<S: Storage>(&self, storage: &mut S) {
         let mut space = prefixed(b"lobby", storage);
         let mut bucket = typed::<_, Locator>(&mut space);
@@ -47,8 +96,182 @@ impl Locator {
     }
 }
 
-pub fn lobby_game<S: Storage>(storage: &mut S) -> Singleton<S, Option<[u8; 32]>> {
-    singleton(storage, b"lobby_game")
+/// One-time code that seats the opponent in a private game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Invite {
+    #[serde(skip)]
+    id: [u8; 32],
+    /// Locator of the creator
+    pub locator: [u8; 32],
+    pub used: bool,
+}
+
+impl Invite {
+    pub fn new(id: [u8; 32], locator: [u8; 32]) -> Invite {
+        Invite {
+            id: id,
+            locator: locator,
+            used: false,
+        }
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) {
+        let mut space = prefixed(b"invite", storage);
+        let mut bucket = typed::<_, Invite>(&mut space);
+        bucket.save(&self.id, &self).unwrap();
+    }
+
+    pub fn load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Self> {
+        let space = prefixed_read(b"invite", storage);
+        let bucket = typed_read::<_, Invite>(&space);
+        let data = bucket.load(&id)?;
+        Ok(Self { id, ..data })
+    }
+
+    pub fn may_load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Option<Self>> {
+        let space = prefixed_read(b"invite", storage);
+        let bucket = typed_read::<_, Invite>(&space);
+        bucket
+            .may_load(&id)
+            .map(|maybe| maybe.map(|data| Self { id, ..data }))
+    }
+}
+
+pub const GAME_INDEX: &[u8] = b"game_index";
+pub const OPEN_PRIVATE_INDEX: &[u8] = b"open_private_index";
+
+/// Appends id to an index. Storage can't be iterated so listings page through indexes by position.
+pub fn push_index<S: Storage>(storage: &mut S, index: &[u8], id: [u8; 32]) -> StdResult<u64> {
+    let pos = index_len(storage, index)?;
+    let mut space = prefixed(index, storage);
+    typed::<_, [u8; 32]>(&mut space).save(&pos.to_be_bytes(), &id)?;
+    typed::<_, u64>(&mut space).save(b"len", &(pos + 1))?;
+    Ok(pos)
+}
+
+/// Index of the locators a player has joined with
+pub fn player_index(player: &HumanAddr) -> Vec<u8> {
+    [b"player_index".as_ref(), player.as_str().as_bytes()].concat()
+}
+
+pub fn index_len<S: Storage>(storage: &S, index: &[u8]) -> StdResult<u64> {
+    let space = prefixed_read(index, storage);
+    Ok(typed_read::<_, u64>(&space).may_load(b"len")?.unwrap_or(0))
+}
+
+pub fn index_get<S: Storage>(storage: &S, index: &[u8], pos: u64) -> StdResult<[u8; 32]> {
+    let space = prefixed_read(index, storage);
+    typed_read::<_, [u8; 32]>(&space).load(&pos.to_be_bytes())
+}
+
+/// Game ids come from a counter so that a new game never lands on an old one
+pub fn next_game_id<S: Storage>(storage: &mut S) -> StdResult<[u8; 32]> {
+    let mut counter = singleton::<_, u64>(storage, b"game_count");
+    let count = counter.may_load()?.unwrap_or(0) + 1;
+    counter.save(&count)?;
+    let mut id = [0u8; 32];
+    id[24..].copy_from_slice(&count.to_be_bytes());
+    Ok(id)
+}
+
+pub fn update_stats<S: Storage>(
+    storage: &mut S,
+    action: impl FnOnce(&mut ContractStats),
+) -> StdResult<()> {
+    let mut stats = singleton::<_, ContractStats>(storage, b"stats");
+    let mut data = stats.may_load()?.unwrap_or_default();
+    action(&mut data);
+    stats.save(&data)
+}
+
+pub fn load_stats<S: Storage>(storage: &S) -> StdResult<ContractStats> {
+    Ok(singleton_read::<_, ContractStats>(storage, b"stats")
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn update_player_stats<S: Storage>(
+    storage: &mut S,
+    player: &HumanAddr,
+    action: impl FnOnce(&mut PlayerStats),
+) -> StdResult<()> {
+    let mut space = prefixed(b"player_stats", storage);
+    let mut bucket = typed::<_, PlayerStats>(&mut space);
+    let mut data = bucket
+        .may_load(player.as_str().as_bytes())?
+        .unwrap_or_default();
+    action(&mut data);
+    bucket.save(player.as_str().as_bytes(), &data)
+}
+
+pub fn load_player_stats<S: Storage>(storage: &S, player: &HumanAddr) -> StdResult<PlayerStats> {
+    let space = prefixed_read(b"player_stats", storage);
+    let bucket = typed_read::<_, PlayerStats>(&space);
+    Ok(bucket
+        .may_load(player.as_str().as_bytes())?
+        .unwrap_or_default())
+}
+
+/// Address of the contract, permits name the contracts they work with but queries get no env
+pub fn contract_address<S: Storage>(storage: &mut S) -> Singleton<S, HumanAddr> {
+    singleton(storage, b"contract_address")
+}
+
+pub fn load_contract_address<S: Storage>(storage: &S) -> StdResult<HumanAddr> {
+    singleton_read(storage, b"contract_address").load()
+}
+
+pub fn admin<S: Storage>(storage: &mut S) -> Singleton<S, HumanAddr> {
+    singleton(storage, b"admin")
+}
+
+pub fn load_admin<S: Storage>(storage: &S) -> StdResult<HumanAddr> {
+    singleton_read(storage, b"admin").load()
+}
+
+/// Bankroll the contract plays house games from
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct House {
+    pub bankroll: Uint128,
+    pub exposure: Uint128,
+    pub exposure_height: u64,
+}
+
+impl House {
+    /// Moves the house's stake for a new game out of the bankroll
+    pub fn reserve(&mut self, stake: Uint128, height: u64) -> StdResult<()> {
+        if self.exposure_height != height {
+            self.exposure = Uint128(0);
+            self.exposure_height = height;
+        }
+        if stake.u128() > self.bankroll.u128() {
+            return Err(StdError::generic_err("forbidden house bankroll too low"));
+        }
+        if self.exposure.u128() + stake.u128() > MAX_HOUSE_EXPOSURE_PER_BLOCK {
+            return Err(StdError::generic_err("forbidden house exposure limit"));
+        }
+        self.bankroll = Uint128(self.bankroll.u128() - stake.u128());
+        self.exposure += stake;
+        Ok(())
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        singleton(storage, b"house").save(self)
+    }
+
+    pub fn load<S: Storage>(storage: &S) -> StdResult<Self> {
+        Ok(singleton_read(storage, b"house")
+            .may_load()?
+            .unwrap_or_default())
+    }
+}
+
+/// Public lobby, free players wait in a lobby of their own
+pub fn lobby_game<S: Storage>(storage: &mut S, free_play: bool) -> Singleton<S, Option<[u8; 32]>> {
+    match free_play {
+        false => singleton(storage, b"lobby_game"),
+        true => singleton(storage, b"free_lobby_game"),
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -62,12 +285,44 @@ pub struct Game {
     pub player2: HumanAddr,
     pub player2_handsign: Option<Handsign>,
     pub player2_wins: u8,
-    pub last_play_height: u64,
     pub game_over: bool,
+    /// Amount staked by each player, winner takes both
+    pub stake: Uint128,
+    pub deadline: DeadlinePolicy,
+    /// Height or time in the unit of the deadline policy when the current round started
+    pub round_start: u64,
+    pub player1_time_bank: u64,
+    pub player2_time_bank: u64,
+    pub outcome: Option<GameOutcome>,
+    pub rematch: Option<Rematch>,
+    /// Series the game belongs to, the series holds the stakes
+    pub series: Option<[u8; 32]>,
+    /// Locators whose stakes the game holds
+    pub locators: Vec<[u8; 32]>,
+    /// Player2 is the contract playing from the house bankroll
+    pub house: bool,
+    /// Block height the house drew its hidden move at, the round resolves in a later block
+    pub house_move_height: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Rematch {
+    pub offered_by: HumanAddr,
+    /// New locator of the player who offered, it points to the rematch game
+    pub locator: [u8; 32],
+    pub started: bool,
 }
 
 impl Game {
-    pub fn new(id: [u8; 32], player1: HumanAddr, player2: HumanAddr) -> Game {
+    pub fn new(
+        id: [u8; 32],
+        player1: HumanAddr,
+        player2: HumanAddr,
+        stake: Uint128,
+        deadline: DeadlinePolicy,
+        env: &Env,
+        locators: Vec<[u8; 32]>,
+    ) -> Game {
         Game {
             id: id,
             round: 1,
@@ -77,8 +332,123 @@ impl Game {
             player2: player2,
             player2_handsign: None,
             player2_wins: 0,
-            last_play_height: 0,
             game_over: false,
+            stake: stake,
+            deadline: deadline,
+            round_start: deadline.now(env),
+            player1_time_bank: deadline.duration,
+            player2_time_bank: deadline.duration,
+            outcome: None,
+            rematch: None,
+            series: None,
+            locators: locators,
+            house: false,
+            house_move_height: None,
+        }
+    }
+
+    /// Starts the next game of a series in place of the finished one
+    pub fn restart(&mut self, start: u64) {
+        self.round = 1;
+        self.player1_handsign = None;
+        self.player1_wins = 0;
+        self.player2_handsign = None;
+        self.player2_wins = 0;
+        self.game_over = false;
+        self.outcome = None;
+        self.round_start = start;
+        self.player1_time_bank = self.deadline.duration;
+        self.player2_time_bank = self.deadline.duration;
+    }
+
+    /// Ends the game with winner taking both stakes, returns the amount to pay out
+    pub fn finish(&mut self, winner: HumanAddr, reason: EndReason, height: u64) -> Uint128 {
+        let payout = Uint128(self.stake.u128() * 2);
+        self.game_over = true;
+        self.outcome = Some(GameOutcome {
+            winner: Some(winner),
+            reason,
+            payout,
+            end_height: height,
+        });
+        payout
+    }
+
+    /// Ends the game without a winner, stakes are returned to the players
+    pub fn abort(&mut self, height: u64) {
+        self.game_over = true;
+        self.outcome = Some(GameOutcome {
+            winner: None,
+            reason: EndReason::Aborted,
+            payout: Uint128(0),
+            end_height: height,
+        });
+    }
+
+    /// Height or time from which the game can be aborted, none once either player has played this round
+    pub fn abandon_deadline(&self) -> Option<u64> {
+        match (self.player1_deadline(), self.player2_deadline()) {
+            (Some(d1), Some(d2)) => Some(d1.max(d2)),
+            _ => None,
+        }
+    }
+
+    /// Neither player has played this round and both ran out of time
+    pub fn abandoned(&self, now: u64) -> bool {
+        self.abandon_deadline()
+            .map_or(false, |deadline| now >= deadline)
+    }
+
+    /// Stops the clock of player who moved at `now` and adds the increment to their bank
+    pub fn charge_clock(&mut self, player: &HumanAddr, now: u64) {
+        let elapsed = now.saturating_sub(self.round_start);
+        let increment = self.deadline.increment;
+        let bank = if *player == self.player1 {
+            &mut self.player1_time_bank
+        } else {
+            &mut self.player2_time_bank
+        };
+        *bank = bank.saturating_sub(elapsed) + increment;
+    }
+
+    /// Height or time at which player1 runs out of time, none if player1 has played this round
+    pub fn player1_deadline(&self) -> Option<u64> {
+        match self.player1_handsign {
+            None => Some(self.round_start + self.player1_time_bank),
+            Some(_) => None,
+        }
+    }
+
+    /// Height or time at which player2 runs out of time, none if player2 has played this round
+    pub fn player2_deadline(&self) -> Option<u64> {
+        match self.player2_played() {
+            false => Some(self.round_start + self.player2_time_bank),
+            true => None,
+        }
+    }
+
+    /// The house draws its move in the player's call, so it is never behind
+    pub fn player2_played(&self) -> bool {
+        self.house || self.player2_handsign.is_some()
+    }
+
+    /// Height or time from which player can claim the opponent's inactivity, none until player has played
+    pub fn claim_deadline(&self, player: &HumanAddr) -> Option<u64> {
+        if *player == self.player1 && self.player1_handsign.is_some() {
+            self.player2_deadline()
+        } else if *player == self.player2 && self.player2_played() {
+            self.player1_deadline()
+        } else {
+            None
+        }
+    }
+
+    /// Earliest deadline of players who haven't played this round
+    pub fn deadline(&self) -> u64 {
+        match (self.player1_deadline(), self.player2_deadline()) {
+            (Some(d1), Some(d2)) => d1.min(d2),
+            (Some(d), None) | (None, Some(d)) => d,
+            (None, None) => self.round_start,
         }
     }
 
@@ -103,3 +473,112 @@ impl Game {
             .map(|maybe| maybe.map(|game| Self { id, ..game }))
     }
 }
+
+/// Address a player lets act on their behalf in a game, payouts still go to the player
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SessionKey {
+    pub address: HumanAddr,
+    /// Block height the key stops working at
+    pub expires_at: u64,
+}
+
+impl SessionKey {
+    pub fn save<S: Storage>(&self, storage: &mut S, game: [u8; 32], player: &HumanAddr) {
+        let mut space = prefixed(b"session", storage);
+        let mut bucket = typed::<_, SessionKey>(&mut space);
+        bucket
+            .save(&[&game[..], player.as_str().as_bytes()].concat(), &self)
+            .unwrap();
+    }
+
+    pub fn may_load<S: Storage>(
+        storage: &S,
+        game: [u8; 32],
+        player: &HumanAddr,
+    ) -> StdResult<Option<Self>> {
+        let space = prefixed_read(b"session", storage);
+        let bucket = typed_read::<_, SessionKey>(&space);
+        bucket.may_load(&[&game[..], player.as_str().as_bytes()].concat())
+    }
+}
+
+/// Best-of series between two players, stakes are escrowed for the whole series
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Series {
+    #[serde(skip)]
+    id: [u8; 32],
+    pub player1: HumanAddr,
+    pub player2: HumanAddr,
+    pub games: u8,
+    pub player1_wins: u8,
+    pub player2_wins: u8,
+    pub stake: Uint128,
+    pub results: Vec<GameOutcome>,
+    pub outcome: Option<GameOutcome>,
+}
+
+impl Series {
+    pub fn new(
+        id: [u8; 32],
+        player1: HumanAddr,
+        player2: HumanAddr,
+        games: u8,
+        stake: Uint128,
+    ) -> Series {
+        Series {
+            id: id,
+            player1: player1,
+            player2: player2,
+            games: games,
+            player1_wins: 0,
+            player2_wins: 0,
+            stake: stake,
+            results: vec![],
+            outcome: None,
+        }
+    }
+
+    /**
+     * Records the outcome of a finished game.
+     * Series ends when a player wins the majority of games, or right away when
+     * a game ends by inactivity. Returns the payout for the series winner.
+     */
+    pub fn record(&mut self, outcome: GameOutcome) -> Uint128 {
+        let winner = outcome.winner.clone();
+        if winner == Some(self.player1.clone()) {
+            self.player1_wins += 1;
+        } else if winner == Some(self.player2.clone()) {
+            self.player2_wins += 1;
+        }
+        let wins_to_finish = self.games / 2 + 1;
+        let decided = self.player1_wins == wins_to_finish
+            || self.player2_wins == wins_to_finish
+            || outcome.reason == EndReason::Inactivity;
+        self.results.push(outcome.clone());
+        if !decided {
+            return Uint128(0);
+        }
+        let payout = Uint128(self.stake.u128() * 2);
+        self.outcome = Some(GameOutcome { payout, ..outcome });
+        payout
+    }
+
+    /// Ends the series without a winner when one of its games was aborted
+    pub fn abort(&mut self, outcome: GameOutcome) {
+        self.results.push(outcome.clone());
+        self.outcome = Some(outcome);
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) {
+        let mut space = prefixed(b"series", storage);
+        let mut bucket = typed::<_, Series>(&mut space);
+        bucket.save(&self.id, &self).unwrap();
+    }
+
+    pub fn load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Self> {
+        let mut space = prefixed_read(b"series", storage);
+        let bucket = typed_read::<_, Series>(&mut space);
+        let series = bucket.load(&id)?;
+        Ok(Self { id, ..series })
+    }
+}
@@ -0,0 +1,430 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{prefixed, prefixed_read, typed, typed_read};
+
+use crate::conf::TEAM_WINS_TO_FINISH;
+use crate::msg::{DeadlinePolicy, EndReason, Handsign, Team, TeamOutcome};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TeamPlayer {
+    pub address: HumanAddr,
+    pub locator: [u8; 32],
+    pub handsign: Option<Handsign>,
+    pub time_bank: u64,
+}
+
+/**
+ * Game between two teams of two. Each round the first players of the teams duel
+ * and so do the second players, every duel won scores a point for the team.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TeamGame {
+    #[serde(skip)]
+    id: [u8; 32],
+    /// Amount staked by each player, the winning team splits all stakes
+    pub stake: Uint128,
+    pub deadline: DeadlinePolicy,
+    pub team_a: Vec<TeamPlayer>,
+    pub team_b: Vec<TeamPlayer>,
+    pub team_a_wins: u8,
+    pub team_b_wins: u8,
+    /// Duels of the current round that have been decided
+    pub duels_done: [bool; 2],
+    pub started: bool,
+    pub round: u16,
+    /// Height or time in the unit of the deadline policy when the current round started
+    pub round_start: u64,
+    pub outcome: Option<TeamOutcome>,
+}
+
+impl TeamGame {
+    pub fn new(id: [u8; 32], stake: Uint128, deadline: DeadlinePolicy) -> TeamGame {
+        TeamGame {
+            id: id,
+            stake: stake,
+            deadline: deadline,
+            team_a: vec![],
+            team_b: vec![],
+            team_a_wins: 0,
+            team_b_wins: 0,
+            duels_done: [false; 2],
+            started: false,
+            round: 1,
+            round_start: 0,
+            outcome: None,
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &TeamPlayer> {
+        self.team_a.iter().chain(self.team_b.iter())
+    }
+
+    /// Seats a player in a team, the game starts at `now` once both teams are full
+    pub fn seat(
+        &mut self,
+        address: HumanAddr,
+        locator: [u8; 32],
+        team: Team,
+        now: u64,
+    ) -> StdResult<()> {
+        if self.started {
+            return Err(StdError::generic_err("forbidden game already started"));
+        }
+        if self.players().any(|p| p.address == address) {
+            return Err(StdError::generic_err("forbidden already in game"));
+        }
+        let time_bank = self.deadline.duration;
+        let members = self.team_mut(team);
+        if members.len() == 2 {
+            return Err(StdError::generic_err("forbidden team full"));
+        }
+        members.push(TeamPlayer {
+            address,
+            locator,
+            handsign: None,
+            time_bank,
+        });
+        if self.team_a.len() == 2 && self.team_b.len() == 2 {
+            self.started = true;
+            self.round_start = now;
+        }
+        Ok(())
+    }
+
+    /// Takes a player out of a game that hasn't started
+    pub fn unseat(&mut self, address: &HumanAddr) -> StdResult<TeamPlayer> {
+        if self.started {
+            return Err(StdError::generic_err("forbidden game already started"));
+        }
+        for team in &[Team::A, Team::B] {
+            let members = self.team_mut(*team);
+            if let Some(i) = members.iter().position(|p| p.address == *address) {
+                return Ok(members.remove(i));
+            }
+        }
+        Err(StdError::generic_err("You are not a player"))
+    }
+
+    /// Height or time at which the player runs out of time, none if their duel needs nothing from them
+    pub fn player_deadline(&self, team: Team, duel: usize) -> Option<u64> {
+        let player = &self.team(team)[duel];
+        match (self.duels_done[duel], player.handsign) {
+            (false, None) => Some(self.round_start + player.time_bank),
+            _ => None,
+        }
+    }
+
+    /// Plays the player's handsign at `now` and scores their duel once the counterpart has played
+    pub fn play(&mut self, address: &HumanAddr, handsign: Handsign, now: u64) -> StdResult<()> {
+        let (team, duel) = self.seat_of(address)?;
+        if self.duels_done[duel] || self.team(team)[duel].handsign.is_some() {
+            return Err(StdError::generic_err("already_played"));
+        }
+        let elapsed = now.saturating_sub(self.round_start);
+        let increment = self.deadline.increment;
+        let player = &mut self.team_mut(team)[duel];
+        player.handsign = Some(handsign);
+        player.time_bank = player.time_bank.saturating_sub(elapsed) + increment;
+
+        if let Some(other) = self.team(team.other())[duel].handsign {
+            if handsign.beats(other) {
+                self.score(team);
+            } else if handsign != other {
+                self.score(team.other());
+            }
+            self.duels_done[duel] = true;
+        }
+        self.end_round(now);
+        Ok(())
+    }
+
+    /**
+     * Awards the claimant's duel to their team when the counterpart ran out of time.
+     * Only the counterpart's duel is forfeited, the other duel of the round goes on.
+     * A duel where both players ran out of time ends with no result, any player can close it.
+     */
+    pub fn time_out(&mut self, address: &HumanAddr, now: u64) -> StdResult<()> {
+        let (team, duel) = self.seat_of(address)?;
+        if self.duels_done[duel] || self.team(team)[duel].handsign.is_none() {
+            return self.close_abandoned(now);
+        }
+        match self.player_deadline(team.other(), duel) {
+            Some(deadline) if now >= deadline => {}
+            _ => {
+                return Err(StdError::generic_err(
+                    "under deadline for claiming inactivity",
+                ))
+            }
+        }
+        self.score(team);
+        self.duels_done[duel] = true;
+        self.end_round(now);
+        Ok(())
+    }
+
+    /// Ends the duels whose players both ran out of time with no result
+    fn close_abandoned(&mut self, now: u64) -> StdResult<()> {
+        let abandoned: Vec<usize> = (0..2).filter(|d| self.duel_abandoned(*d, now)).collect();
+        if abandoned.is_empty() {
+            return Err(StdError::generic_err("unable to claim inactivity"));
+        }
+        let elapsed = now.saturating_sub(self.round_start);
+        for duel in abandoned {
+            for team in &[Team::A, Team::B] {
+                let player = &mut self.team_mut(*team)[duel];
+                player.time_bank = player.time_bank.saturating_sub(elapsed);
+            }
+            self.duels_done[duel] = true;
+        }
+        self.end_round(now);
+        Ok(())
+    }
+
+    /// Both duels of the round ran out of time without a move, the game can only be aborted
+    pub fn abandoned(&self, now: u64) -> bool {
+        self.started && self.outcome.is_none() && (0..2).all(|d| self.duel_abandoned(d, now))
+    }
+
+    /// Whether both players of the duel ran out of time without playing
+    fn duel_abandoned(&self, duel: usize, now: u64) -> bool {
+        match (
+            self.player_deadline(Team::A, duel),
+            self.player_deadline(Team::B, duel),
+        ) {
+            (Some(a), Some(b)) => now >= a.max(b),
+            _ => false,
+        }
+    }
+
+    /// Team that reached the wins to finish ahead of the other, decided between rounds only
+    pub fn winner(&self) -> Option<Team> {
+        let mid_round = self.duels_done.iter().any(|done| *done);
+        let lead = self.team_a_wins.max(self.team_b_wins);
+        if mid_round || lead < TEAM_WINS_TO_FINISH || self.team_a_wins == self.team_b_wins {
+            return None;
+        }
+        match self.team_a_wins > self.team_b_wins {
+            true => Some(Team::A),
+            false => Some(Team::B),
+        }
+    }
+
+    /// Ends the game with the winning team splitting all stakes, returns each member's share
+    pub fn finish(&mut self, winner: Team, reason: EndReason, height: u64) -> Uint128 {
+        let share = Uint128(self.stake.u128() * 2);
+        self.outcome = Some(TeamOutcome {
+            winner: Some(winner),
+            reason,
+            payout: share,
+            end_height: height,
+        });
+        share
+    }
+
+    /// Ends an abandoned game with no winner, returns the stake refunded to each player
+    pub fn abort(&mut self, height: u64) -> Uint128 {
+        self.outcome = Some(TeamOutcome {
+            winner: None,
+            reason: EndReason::Aborted,
+            payout: self.stake,
+            end_height: height,
+        });
+        self.stake
+    }
+
+    pub fn team(&self, team: Team) -> &Vec<TeamPlayer> {
+        match team {
+            Team::A => &self.team_a,
+            Team::B => &self.team_b,
+        }
+    }
+
+    fn team_mut(&mut self, team: Team) -> &mut Vec<TeamPlayer> {
+        match team {
+            Team::A => &mut self.team_a,
+            Team::B => &mut self.team_b,
+        }
+    }
+
+    fn seat_of(&self, address: &HumanAddr) -> StdResult<(Team, usize)> {
+        for team in &[Team::A, Team::B] {
+            if let Some(i) = self.team(*team).iter().position(|p| p.address == *address) {
+                return Ok((*team, i));
+            }
+        }
+        Err(StdError::generic_err("You are not a player"))
+    }
+
+    fn score(&mut self, team: Team) {
+        match team {
+            Team::A => self.team_a_wins += 1,
+            Team::B => self.team_b_wins += 1,
+        }
+    }
+
+    fn end_round(&mut self, now: u64) {
+        if self.duels_done.iter().any(|done| !done) {
+            return;
+        }
+        for player in self.team_a.iter_mut().chain(self.team_b.iter_mut()) {
+            player.handsign = None;
+        }
+        self.duels_done = [false; 2];
+        self.round += 1;
+        self.round_start = now;
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) {
+        let mut space = prefixed(b"team_game", storage);
+        let mut bucket = typed::<_, TeamGame>(&mut space);
+        bucket.save(&self.id, &self).unwrap();
+    }
+
+    pub fn load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Self> {
+        let mut space = prefixed_read(b"team_game", storage);
+        let bucket = typed_read::<_, TeamGame>(&mut space);
+        let game = bucket.load(&id)?;
+        Ok(Self { id, ..game })
+    }
+
+    pub fn may_load<S: Storage>(storage: &S, id: [u8; 32]) -> StdResult<Option<Self>> {
+        let mut space = prefixed_read(b"team_game", storage);
+        let bucket = typed_read::<_, TeamGame>(&mut space);
+        bucket
+            .may_load(&id)
+            .map(|maybe| maybe.map(|data| Self { id, ..data }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Handsign::{PAPR, ROCK, SCRS};
+
+    fn game() -> TeamGame {
+        let mut game = TeamGame::new([0u8; 32], Uint128(10), DeadlinePolicy::blocks(10, 5));
+        for (i, address) in ["a1", "b1", "a2", "b2"].iter().enumerate() {
+            let team = if address.starts_with('a') {
+                Team::A
+            } else {
+                Team::B
+            };
+            game.seat(HumanAddr::from(*address), [i as u8; 32], team, 100)
+                .unwrap();
+        }
+        game
+    }
+
+    fn play(game: &mut TeamGame, address: &str, handsign: Handsign, now: u64) {
+        game.play(&HumanAddr::from(address), handsign, now).unwrap();
+    }
+
+    #[test]
+    fn starts_when_teams_full() {
+        let mut game = TeamGame::new([0u8; 32], Uint128(10), DeadlinePolicy::default());
+        game.seat(HumanAddr::from("a1"), [1u8; 32], Team::A, 5)
+            .unwrap();
+        game.seat(HumanAddr::from("a2"), [2u8; 32], Team::A, 5)
+            .unwrap();
+        assert_eq!(
+            game.seat(HumanAddr::from("a3"), [3u8; 32], Team::A, 5)
+                .unwrap_err(),
+            StdError::generic_err("forbidden team full")
+        );
+        game.seat(HumanAddr::from("b1"), [4u8; 32], Team::B, 5)
+            .unwrap();
+        assert_eq!(game.started, false);
+        game.seat(HumanAddr::from("b2"), [5u8; 32], Team::B, 6)
+            .unwrap();
+        assert_eq!(game.started, true);
+        assert_eq!(game.round_start, 6);
+    }
+
+    #[test]
+    fn duel_wins_summed_per_team() {
+        let mut game = game();
+        play(&mut game, "a1", ROCK, 101);
+        play(&mut game, "b1", SCRS, 101);
+        assert_eq!(game.round, 1);
+        play(&mut game, "b2", PAPR, 102);
+        play(&mut game, "a2", ROCK, 102);
+        assert_eq!((game.team_a_wins, game.team_b_wins), (1, 1));
+        assert_eq!(game.round, 2);
+
+        for _ in 1..TEAM_WINS_TO_FINISH {
+            play(&mut game, "a1", PAPR, 103);
+            play(&mut game, "b1", ROCK, 103);
+            play(&mut game, "a2", SCRS, 103);
+            play(&mut game, "b2", ROCK, 103);
+        }
+        // reaching the wins to finish level doesn't decide the game
+        assert_eq!((game.team_a_wins, game.team_b_wins), (5, 5));
+        assert_eq!(game.winner(), None);
+
+        play(&mut game, "a1", PAPR, 104);
+        play(&mut game, "b1", ROCK, 104);
+        play(&mut game, "a2", SCRS, 104);
+        // tied duel scores for neither team
+        play(&mut game, "b2", SCRS, 104);
+        assert_eq!(game.winner(), Some(Team::A));
+    }
+
+    #[test]
+    fn inactivity_forfeits_only_sub_duel() {
+        let mut game = game();
+        play(&mut game, "a1", ROCK, 101);
+        assert_eq!(
+            game.time_out(&HumanAddr::from("a1"), 109).unwrap_err(),
+            StdError::generic_err("under deadline for claiming inactivity")
+        );
+        game.time_out(&HumanAddr::from("a1"), 110).unwrap();
+        assert_eq!((game.team_a_wins, game.team_b_wins), (1, 0));
+        assert_eq!(game.duels_done, [true, false]);
+
+        // the second duel is still played out
+        assert_eq!(
+            game.play(&HumanAddr::from("b1"), PAPR, 111).unwrap_err(),
+            StdError::generic_err("already_played")
+        );
+        play(&mut game, "a2", ROCK, 111);
+        play(&mut game, "b2", PAPR, 111);
+        assert_eq!((game.team_a_wins, game.team_b_wins), (1, 1));
+        assert_eq!(game.round, 2);
+    }
+
+    #[test]
+    fn abandoned_duel_scores_for_neither_team() {
+        let mut game = game();
+        play(&mut game, "a1", ROCK, 101);
+        play(&mut game, "b1", SCRS, 101);
+        assert_eq!(
+            game.time_out(&HumanAddr::from("a1"), 109).unwrap_err(),
+            StdError::generic_err("unable to claim inactivity")
+        );
+        // a teammate of an idle player closes the duel once both are out of time
+        game.time_out(&HumanAddr::from("a1"), 110).unwrap();
+        assert_eq!((game.team_a_wins, game.team_b_wins), (1, 0));
+        assert_eq!(game.round, 2);
+        assert_eq!(game.team_a[1].time_bank, 0);
+        assert_eq!(game.team_b[1].time_bank, 0);
+    }
+
+    #[test]
+    fn abandoned_round_aborts() {
+        let mut game = game();
+        // one abandoned duel is closed and the other goes on
+        play(&mut game, "a2", ROCK, 101);
+        assert_eq!(game.abandoned(110), false);
+        game.time_out(&HumanAddr::from("a1"), 110).unwrap();
+        assert_eq!(game.duels_done, [true, false]);
+
+        let mut game = self::game();
+        assert_eq!(game.abandoned(109), false);
+        assert_eq!(game.abandoned(110), true);
+        assert_eq!(game.abort(110), Uint128(10));
+        assert_eq!(game.outcome.as_ref().unwrap().winner, None);
+        assert_eq!(game.abandoned(110), false);
+    }
+}
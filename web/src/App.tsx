@@ -111,18 +111,20 @@ const playGame = async (
   setGame: Function,
   loadGame: Function,
   enqueueSnackbar: Function,
-  locator?: string,
+  invite?: string,
 ) => {
   const currentGame = loadGame();
   if (currentGame !== undefined) {
     setGame(currentGame);
     return;
   }
-  const game = Game.create(contract, privateGame, locator);
+  const game = Game.create(contract, privateGame, invite);
   setGame(game);
-  const method = privateGame ? 'private_game' : 'join_game';
+  const msg = privateGame
+    ? { private_game: { locator: game.locator, invite: game.invite } }
+    : { join_game: { locator: game.locator } };
   try {
-    await client.execute(contract, { [method]: { locator: game.locator } }, undefined, [
+    await client.execute(contract, msg, undefined, [
       {
         amount: '10000000',
         denom: 'uscrt',
@@ -154,10 +156,10 @@ const routeUrl = (
   enqueueSnackbar: Function,
 ) => {
   const url = new URL(window.location.href);
-  const joinLocator = url.searchParams.get('game');
-  if (client && joinLocator) {
+  const invite = url.searchParams.get('game');
+  if (client && invite) {
     setTimeout(() => {
-      playGame(client, config.contract, true, setGame, loadGame, enqueueSnackbar, joinLocator);
+      playGame(client, config.contract, true, setGame, loadGame, enqueueSnackbar, invite);
     }, 0);
     window.history.pushState('', '', document.location.origin);
   }
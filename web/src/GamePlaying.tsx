@@ -118,7 +118,7 @@ const GamePlaying = (props: Props) => {
 
   if (game.stage === Game.Stage.Lobby) {
     const query = new URLSearchParams();
-    query.append('game', game.locator);
+    query.append('game', game.invite || '');
     const url = `${document.location.origin}?${query.toString()}`;
     return (
       <Container fixed maxWidth="sm">
@@ -185,7 +185,13 @@ const GamePlaying = (props: Props) => {
             {displayContent === DisplayContent.SelectedHandsign && game.lastHandsign && (
               <HandsignImg handsign={game.lastHandsign} />
             )}
-            {displayContent === DisplayContent.Ending && <p>You {game.won ? 'won' : 'lost'}</p>}
+            {displayContent === DisplayContent.Ending && (
+              <p>
+                You {game.won ? 'won' : 'lost'}
+                {game.endReason === Msg.EndReason.Inactivity &&
+                  (game.won ? ', opponent ran out of time' : ', you ran out of time')}
+              </p>
+            )}
             {displayContent === DisplayContent.PickHandsign && (
               <div>
                 <Grid container justify="center" alignItems="center" spacing={3}>
@@ -5,12 +5,14 @@ interface Game {
   readonly contract: string;
   readonly privateGame: boolean;
   readonly locator: string;
+  readonly invite: string | undefined;
   readonly createdAt: number;
   readonly updatedAt: number;
   readonly playerNumber: number | undefined;
   readonly stage: Stage;
   readonly round: number;
   readonly won: boolean;
+  readonly endReason: Msg.EndReason | undefined;
   readonly wins: number;
   readonly losses: number;
   readonly played: boolean;
@@ -27,6 +29,7 @@ interface TickUpdate {
   readonly stage: Stage;
   readonly round: number;
   readonly won: boolean;
+  readonly endReason: Msg.EndReason | undefined;
   readonly wins: number;
   readonly losses: number;
   readonly played: boolean;
@@ -59,12 +62,14 @@ const defaults: Game = Object.freeze({
   contract: '',
   privateGame: false,
   locator: '',
+  invite: undefined,
   createdAt: 0,
   updatedAt: 0,
   playerNumber: undefined,
   stage: Stage.Creating,
   round: 1,
   won: false,
+  endReason: undefined,
   wins: 0,
   losses: 0,
   played: false,
@@ -75,18 +80,21 @@ const defaults: Game = Object.freeze({
   lossDeadlineSeconds: undefined,
 });
 
-const create = (contract: string, privateGame: boolean, joinLocator?: string): Game => {
-  let locator = joinLocator;
+const randomHex = (): string => {
+  const bytes = new Uint8Array(32);
+  crypto.getRandomValues(bytes);
+  return Buffer.from(bytes).toString('hex');
+};
+
+const create = (contract: string, privateGame: boolean, joinInvite?: string): Game => {
+  const locator = randomHex();
+  let invite: string | undefined;
   let playerNumber: number | undefined;
   let stage = Stage.Creating;
-  if (!locator) {
-    const randomLocator = new Uint8Array(32);
-    crypto.getRandomValues(randomLocator);
-    locator = Buffer.from(randomLocator).toString('hex');
-  }
 
   if (privateGame) {
-    if (joinLocator !== undefined) {
+    invite = joinInvite || randomHex();
+    if (joinInvite !== undefined) {
       playerNumber = 2;
       stage = Stage.GameOn;
     } else {
@@ -98,6 +106,7 @@ const create = (contract: string, privateGame: boolean, joinLocator?: string): G
     contract,
     privateGame,
     locator,
+    invite,
     playerNumber,
     stage,
     createdAt: Number(new Date()),
@@ -114,6 +123,7 @@ const tick = async (
     stage: game.stage,
     round: game.round,
     won: game.won,
+    endReason: game.endReason,
     wins: game.wins,
     losses: game.losses,
     played: game.played,
@@ -147,14 +157,20 @@ const tick = async (
     game_status: { locator: game.locator },
   });
   const stage = status.game_over ? Stage.Over : Stage.GameOn;
-  const deadlineSeconds = Math.max(0, (status.deadline - height) * 6);
+  const won = !!status.outcome && status.outcome.winner === client.senderAddress;
+  const endReason = status.outcome ? status.outcome.reason : undefined;
+  const deadlineSeconds =
+    status.deadline_unit === Msg.DeadlineUnit.Seconds
+      ? Math.max(0, status.deadline - Math.floor(Number(new Date()) / 1000))
+      : Math.max(0, (status.deadline - height) * 6);
 
   if (game.playerNumber === 1) {
     update = {
       ...update,
       stage,
       round: status.round,
-      won: status.player1_wins >= 3,
+      won,
+      endReason,
       wins: status.player1_wins,
       losses: status.player2_wins,
       played: status.player1_played,
@@ -169,7 +185,8 @@ const tick = async (
       ...update,
       stage,
       round: status.round,
-      won: status.player2_wins >= 3,
+      won,
+      endReason,
       wins: status.player2_wins,
       losses: status.player1_wins,
       played: status.player2_played,
@@ -9,7 +9,53 @@ export interface GameStatusResponse {
   player1_wins: number;
   player2_wins: number;
   deadline: number;
+  deadline_unit: DeadlineUnit;
   game_over: boolean;
+  stake: string;
+  outcome: GameOutcome | null;
+}
+
+export interface HandleAnswer {
+  join_game?: JoinGameAnswer;
+  private_game?: JoinGameAnswer;
+  play_hand?: PlayHandAnswer;
+  claim_inactivity?: ClaimInactivityAnswer;
+}
+
+export interface JoinGameAnswer {
+  game_started: boolean;
+  player1: boolean;
+}
+
+export interface PlayHandAnswer {
+  round_resolved: boolean;
+  round: number;
+  wins: number;
+  opponent_wins: number;
+  game_over: boolean;
+}
+
+export interface ClaimInactivityAnswer {
+  refunded: boolean;
+  game_over: boolean;
+}
+
+export interface GameOutcome {
+  winner: string | null;
+  reason: EndReason;
+  payout: string;
+  end_height: number;
+}
+
+export enum EndReason {
+  WinsReached = 'wins_reached',
+  Inactivity = 'inactivity',
+  Aborted = 'aborted',
+}
+
+export enum DeadlineUnit {
+  Blocks = 'blocks',
+  Seconds = 'seconds',
 }
 
 export enum Handsign {
@@ -17,3 +63,36 @@ export enum Handsign {
   Paper = 'PAPR',
   Scissors = 'SCRS',
 }
+
+export interface ClaimabilityResponse {
+  can_claim: boolean;
+  claimable_from: number | null;
+  deadline_unit: DeadlineUnit;
+  payout: string;
+  abort: boolean;
+}
+
+export interface PlayerGame {
+  index: number;
+  locator: string;
+  stake: string;
+  started: boolean;
+  game_over: boolean;
+  refunded: boolean;
+  won: boolean | null;
+  reason: EndReason | null;
+}
+
+export interface MyGamesResponse {
+  games: PlayerGame[];
+  total: number;
+}
+
+export interface PlayerStats {
+  games_joined: number;
+  games_finished: number;
+  wins: number;
+  total_staked: string;
+  total_won: string;
+  total_refunded: string;
+}